@@ -18,11 +18,7 @@ fn setup_physics(mut commands: Commands) {
     let mesh = &load_gltf_file("./data/environment.gltf".to_string())[0];
     let tri_meshes = mesh.mesh_collection();
     for tri_mesh in tri_meshes {
-        let vertices = tri_mesh
-            .vertices
-            .iter()
-            .map(|vec| Vec3::new(vec[0], vec[1], vec[2]))
-            .collect();
+        let vertices = tri_mesh.glam_vertices();
         let indices = tri_mesh.indices;
         commands
             .spawn(Collider::trimesh(vertices, indices))
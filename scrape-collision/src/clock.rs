@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use crate::errors::CollisionError;
+
+/// Steps per second, what rapier's default integration parameters are tuned for.
+pub const DEFAULT_TICK_RATE: u32 = 60;
+/// Most steps a single [`FixedClock::advance`] runs, so a slow tick can't snowball into ever more steps.
+pub const DEFAULT_MAX_SUBSTEPS: u32 = 5;
+
+/// Turns jittery wall-clock time into steps of exactly `1 / tick_rate` seconds.
+///
+/// Elapsed time is collected in an accumulator and spent a step at a time, whatever is left
+/// over carries into the next advance and gives the interpolation alpha.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedClock {
+    tick_rate: u32,
+    max_substeps: u32,
+    accumulator: Duration,
+}
+
+impl Default for FixedClock {
+    fn default() -> Self {
+        Self {
+            tick_rate: DEFAULT_TICK_RATE,
+            max_substeps: DEFAULT_MAX_SUBSTEPS,
+            accumulator: Duration::ZERO,
+        }
+    }
+}
+
+impl FixedClock {
+    pub fn new(tick_rate: u32, max_substeps: u32) -> Result<Self, CollisionError> {
+        if tick_rate == 0 || max_substeps == 0 {
+            return Err(CollisionError::InvalidConfig(
+                "tick rate and max substeps have to be at least 1".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            tick_rate,
+            max_substeps,
+            accumulator: Duration::ZERO,
+        })
+    }
+
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    pub fn max_substeps(&self) -> u32 {
+        self.max_substeps
+    }
+
+    /// Length of a single step.
+    pub fn dt(&self) -> Duration {
+        Duration::from_secs(1) / self.tick_rate
+    }
+
+    /// Adds the elapsed time and returns how many steps are due, at most `max_substeps`.
+    ///
+    /// Time beyond that is dropped (the simulation slows down) instead of being caught up on later.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        let dt = self.dt();
+        self.accumulator += elapsed;
+
+        let mut steps = 0;
+        while self.accumulator >= dt && steps < self.max_substeps {
+            self.accumulator -= dt;
+            steps += 1;
+        }
+        if self.accumulator >= dt {
+            self.accumulator =
+                Duration::from_nanos((self.accumulator.as_nanos() % dt.as_nanos()) as u64);
+        }
+
+        steps
+    }
+
+    /// Time collected towards the next step.
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// How far we are between the last and the next step, from `0.0` up to `1.0`,
+    /// to interpolate poses for rendering.
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.dt().as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::FixedClock;
+
+    #[test]
+    fn fixed_steps_test() {
+        let mut clock = FixedClock::new(50, 4).unwrap();
+        assert_eq!(clock.dt(), Duration::from_millis(20));
+
+        // Jittery ticks still add up to one step per 20ms
+        assert_eq!(clock.advance(Duration::from_millis(15)), 0);
+        assert_eq!(clock.advance(Duration::from_millis(15)), 1);
+        assert_eq!(clock.accumulator(), Duration::from_millis(10));
+        assert!((clock.alpha() - 0.5).abs() < 1.0e-6);
+        assert_eq!(clock.advance(Duration::from_millis(50)), 3);
+        assert_eq!(clock.accumulator(), Duration::ZERO);
+
+        // A stall only runs the max substeps and drops the rest
+        assert_eq!(clock.advance(Duration::from_millis(1005)), 4);
+        assert_eq!(clock.accumulator(), Duration::from_millis(5));
+
+        assert!(FixedClock::new(0, 4).is_err());
+    }
+}
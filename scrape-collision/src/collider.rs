@@ -1,38 +1,112 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::FixedClock;
+use crate::config::{CharacterControllerConfig, ControllerProfiles};
+use crate::entity::{
+    BodyTransform, DynamicBody, EntityDescriptor, EntityId, EntityKind, EntityState, EntityTag,
+};
+use crate::errors::CollisionError;
+use crate::events::{EventTarget, PhysicsEvent, StepEvents};
+use crate::history::{RecordedPose, TransformHistory, DEFAULT_HISTORY_LENGTH};
+use crate::hitbox::{Hitbox, HitboxPart};
+use crate::layers::{CollisionLayer, CollisionLayers};
+use crate::map::{trigger_hull, trigger_name, MapGeometry};
+use crate::orientation::normalize_quaternion;
+use crate::queries::{AreaHit, HitFilter, QueryHit};
 use crate::rapier::IntoRapier;
+use crate::shape::EntityShape;
+use crate::snapshot::{EntitySnapshot, WorldSnapshot};
+use crate::stance::Stance;
+use crate::surface::{HitDescription, MapSurface};
+use crate::triggers::{TriggerId, TriggerVolume};
 pub use rapier3d::{
     control::CharacterCollision,
     control::KinematicCharacterController,
     geometry::ColliderHandle,
-    parry::query::TOIStatus,
     na::{ArrayStorage, Const, Matrix, Point3},
+    parry::query::TOIStatus,
     prelude::{
         Collider, ColliderBuilder, ColliderSet, QueryFilter, QueryPipeline, RigidBody,
-        RigidBodyBuilder, RigidBodyHandle, RigidBodySet,
+        RigidBodyBuilder, RigidBodyHandle, RigidBodySet, Vector,
     },
 };
+pub use scrape_gltf_loader::mesh::VertexSample;
 
 use rapier3d::{
-    na::{Quaternion, UnitQuaternion}, prelude::{
-        BroadPhase, CCDSolver, ImpulseJointSet, IntegrationParameters, IslandManager,
-        MultibodyJointSet, NarrowPhase, PhysicsPipeline,
-    }
+    na::{Isometry3, Translation3},
+    na::{Quaternion, UnitQuaternion},
+    parry::query::{time_of_impact, PointQueryWithLocation, Ray, RayIntersection},
+    parry::shape::Ball,
+    prelude::{
+        ActiveCollisionTypes, ActiveEvents, BroadPhase, CCDSolver, ImpulseJointSet,
+        IntegrationParameters, InteractionGroups, IslandManager, MultibodyJointSet, NarrowPhase,
+        PhysicsPipeline, RigidBodyType, SharedShape,
+    },
 };
 
-use scrape_gltf_loader::loader::load_gltf_file;
+use scrape_gltf_loader::loader::stream_gltf_file;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Extra distance below the entity in which we still look for the ground it stands on.
+const GROUND_PROBE_MARGIN: f32 = 0.1;
+/// How far below zero a hit normal's dot product with the up vector has to be to count as a ceiling,
+/// so walls don't stop a jump.
+const CEILING_NORMAL_EPSILON: f32 = 1.0e-3;
 
 pub struct Movement {
     pub next_position: Matrix<f32, Const<3>, Const<1>, ArrayStorage<f32, 3, 1>>,
     pub collisions: Vec<CharacterCollision>,
+    /// Whether the entity stands on the ground after the movement
+    pub grounded: bool,
+    /// Whether the ground is steeper than the controller's `min_slope_slide_angle`
+    pub is_sliding_down_slope: bool,
+}
+
+/// Result of sweeping a projectile with [`GameCollider::sweep_projectiles`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectileSweep {
+    /// Translation until the earliest hit, the whole desired one without a hit
+    pub next_position: Vector<f32>,
+    /// Earliest world or entity hit along the travelled segment
+    pub hit: Option<QueryHit>,
+    /// Fraction of the desired translation travelled before the hit, `1.0` without one
+    pub time_of_impact: f32,
+}
+
+/// An entity (or one of its hitboxes) with its collider and its pose at an earlier tick.
+type RewoundEntity = (EventTarget, SharedShape, Isometry3<f32>);
+
+/// A movement to resolve with [`GameCollider::calculate_movements`].
+#[derive(Debug, Clone)]
+pub struct MovementRequest {
+    pub entity_id: EntityId,
+    /// Colliders the entity moves through, e.g. the one of whoever fired a bullet
+    /// (which also excludes its hitboxes)
+    pub exclude_colliders: Vec<ColliderHandle>,
+    pub desired: Vec<f32>,
 }
 
 pub struct GameCollider {
     bodies: RigidBodySet,
     colliders: ColliderSet,
     controller: KinematicCharacterController,
+    controller_profiles: HashMap<String, KinematicCharacterController>,
     query_pipeline: QueryPipeline,
     physics_pipeline: PhysicsPipeline,
+    map_surfaces: HashMap<ColliderHandle, Arc<MapSurface>>,
+    entities: HashMap<EntityId, EntityState>,
+    triggers: HashMap<ColliderHandle, TriggerVolume>,
+    hitboxes: HashMap<ColliderHandle, (EntityId, HitboxPart)>,
+    gravity: Vec<f32>,
+    tick: u64,
+    history: TransformHistory,
+    clock: FixedClock,
 
-    // NOT USED
+    // Physics pipeline state, the step length in `integration_parameters` also drives movements and gravity
     integration_parameters: IntegrationParameters,
     island_manager: IslandManager,
     broad_phase: BroadPhase,
@@ -40,30 +114,94 @@ pub struct GameCollider {
     impulse_joint_set: ImpulseJointSet,
     multibody_joint_set: MultibodyJointSet,
     ccd_solver: CCDSolver,
-    gravity: Vec<f32>,
 }
 
 impl GameCollider {
     pub fn calculate_movement(
         &mut self,
-        entity_handle: RigidBodyHandle,
+        entity_id: EntityId,
         exclude_colliders: Vec<ColliderHandle>,
         desired: Vec<f32>,
-    ) -> Movement {
+    ) -> Result<Movement, CollisionError> {
+        self.calculate_movements(vec![MovementRequest {
+            entity_id,
+            exclude_colliders,
+            desired,
+        }])
+        .remove(0)
+    }
+
+    /// Resolves every movement of a tick against the same query pipeline state, which is only updated once.
+    ///
+    /// The results are in the order of `requests`. Movements are resolved independently of each other,
+    /// so entities in the same batch don't see where the others are moving to. With the `rayon` feature
+    /// they are resolved in parallel.
+    pub fn calculate_movements(
+        &mut self,
+        requests: Vec<MovementRequest>,
+    ) -> Vec<Result<Movement, CollisionError>> {
         self.query_pipeline.update(&self.bodies, &self.colliders);
-        let desired_translation = desired.into_rapier();
-        let entity = self.get_entity(entity_handle);
-        let starting_translation = entity.position();
-        let entity_collider = ColliderBuilder::capsule_y(0.5, 0.2).build();
 
-        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
-        let mut events = Vec::new();
+        #[cfg(feature = "rayon")]
+        let resolved: Vec<_> = requests
+            .par_iter()
+            .map(|request| self.resolve_movement(request))
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let resolved: Vec<_> = requests
+            .iter()
+            .map(|request| self.resolve_movement(request))
+            .collect();
+
+        requests
+            .iter()
+            .zip(resolved)
+            .map(|(request, resolved)| {
+                let (movement, vertical_velocity) = resolved?;
+                let state = self
+                    .entities
+                    .get_mut(&request.entity_id)
+                    .ok_or(CollisionError::UnknownEntity(request.entity_id.body()))?;
+                state.grounded = movement.grounded;
+                state.vertical_velocity = vertical_velocity;
+                Ok(movement)
+            })
+            .collect()
+    }
+
+    /// Moves the entity's shape through the current query pipeline without changing any state,
+    /// returns the movement and the entity's new vertical velocity.
+    fn resolve_movement(
+        &self,
+        request: &MovementRequest,
+    ) -> Result<(Movement, f32), CollisionError> {
+        let entity_id = request.entity_id;
+        if !self.get_entity(entity_id)?.is_kinematic() {
+            return Err(CollisionError::NotKinematic(entity_id.body()));
+        }
+        let dt = self.integration_parameters.dt;
+        let controller = self.get_entity_controller(entity_id)?;
+        let up = controller.up;
+        let vertical_velocity = self
+            .entities
+            .get(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?
+            .vertical_velocity;
+        let desired_translation = request.desired.into_rapier() + *up * vertical_velocity * dt;
+        let starting_translation = *self.get_entity(entity_id)?.position();
+        let entity_collider = self.get_entity_collider(entity_id)?;
 
-        let exclude_fn = |handle, _collider: &_| !exclude_colliders.contains(&handle);
-        let query_filters = QueryFilter::new().predicate(&exclude_fn).exclude_rigid_body(entity_handle);
+        let exclude_fn = |handle, _collider: &_| !request.exclude_colliders.contains(&handle);
+        let query_filters = QueryFilter::new()
+            .predicate(&exclude_fn)
+            .groups(self.get_entity_movement_groups(entity_id)?)
+            .exclude_rigid_body(entity_id.body())
+            // The controller doesn't skip sensors itself, and other players' hitboxes aren't walls
+            .exclude_sensors();
 
-        let calculated_movement = self.controller.move_shape(
-            self.integration_parameters.dt,
+        let mut collisions = Vec::new();
+        let calculated_movement = controller.move_shape(
+            dt,
             &self.bodies,
             &self.colliders,
             &self.query_pipeline,
@@ -71,173 +209,3033 @@ impl GameCollider {
             &starting_translation,
             desired_translation,
             query_filters,
-            move |event| {
-                sender.send(event).unwrap();
-            }, // if we need to fetch any events, use this fn
+            |collision| collisions.push(collision),
+        );
+
+        let grounded = calculated_movement.grounded;
+        // Landing stops the fall and bumping into a ceiling stops the jump
+        let hit_ceiling = collisions
+            .iter()
+            .any(|collision| collision.toi.normal1.dot(&up) < -CEILING_NORMAL_EPSILON);
+        let vertical_velocity = if grounded {
+            vertical_velocity.max(0.0)
+        } else if hit_ceiling {
+            vertical_velocity.min(0.0)
+        } else {
+            vertical_velocity
+        };
+        let is_sliding_down_slope = grounded
+            && self
+                .ground_angle(
+                    entity_id,
+                    starting_translation.translation.vector + calculated_movement.translation,
+                )
+                .is_some_and(|angle| angle > controller.min_slope_slide_angle);
+
+        Ok((
+            Movement {
+                next_position: calculated_movement.translation,
+                collisions,
+                grounded,
+                is_sliding_down_slope,
+            },
+            vertical_velocity,
+        ))
+    }
+
+    /// Projectile mode: sweeps every entity's shape along the whole segment it travels this tick
+    /// and stops at the earliest hit, so fast bullets can't skip through thin walls or players.
+    ///
+    /// Unlike [`Self::calculate_movements`] the entities don't slide along what they hit and aren't pulled by gravity.
+    pub fn sweep_projectiles(
+        &mut self,
+        requests: Vec<MovementRequest>,
+    ) -> Vec<Result<ProjectileSweep, CollisionError>> {
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+
+        #[cfg(feature = "rayon")]
+        let sweeps = requests
+            .par_iter()
+            .map(|request| self.sweep_projectile(request))
+            .collect();
+        #[cfg(not(feature = "rayon"))]
+        let sweeps = requests
+            .iter()
+            .map(|request| self.sweep_projectile(request))
+            .collect();
+        sweeps
+    }
+
+    fn sweep_projectile(
+        &self,
+        request: &MovementRequest,
+    ) -> Result<ProjectileSweep, CollisionError> {
+        let entity_id = request.entity_id;
+        let body = self.get_entity(entity_id)?;
+        if !body.is_kinematic() {
+            return Err(CollisionError::NotKinematic(entity_id.body()));
+        }
+        let entity_collider = self.get_entity_collider(entity_id)?;
+        let desired = request.desired.into_rapier();
+
+        let exclude_fn = |handle, _collider: &_| {
+            let owner = self
+                .hitboxes
+                .get(&handle)
+                .map(|(owner, _)| owner.collider());
+            !request.exclude_colliders.contains(&owner.unwrap_or(handle))
+        };
+        let query_filters = QueryFilter::new()
+            .predicate(&exclude_fn)
+            .groups(self.get_entity_movement_groups(entity_id)?)
+            .exclude_rigid_body(entity_id.body());
+
+        // Casting along the unnormalized translation makes the time of impact the travelled fraction
+        let hit = self.query_pipeline.cast_shape(
+            &self.bodies,
+            &self.colliders,
+            body.position(),
+            &desired,
+            entity_collider.shape(),
+            1.0,
+            true,
+            query_filters,
         );
 
-        while let Ok(event) = receiver.try_recv() {
-            events.push(event);
+        let Some((handle, toi)) = hit else {
+            return Ok(ProjectileSweep {
+                next_position: desired,
+                hit: None,
+                time_of_impact: 1.0,
+            });
+        };
+        Ok(ProjectileSweep {
+            next_position: desired * toi.toi,
+            hit: Some(QueryHit {
+                target: self.event_target(handle),
+                point: toi.witness1,
+                normal: *toi.normal1,
+                distance: desired.norm() * toi.toi,
+                triangle: self.hit_triangle(handle, toi.witness1),
+            }),
+            time_of_impact: toi.toi,
+        })
+    }
+
+    /// Applies one step of gravity to the vertical velocity of every kinematic entity, however many
+    /// movements it resolved. Rapier pulls the dynamic ones itself.
+    fn apply_gravity(&mut self) {
+        let dt = self.integration_parameters.dt;
+        let gravity = self.gravity.into_rapier();
+        let accelerations: Vec<(EntityId, f32)> = self
+            .entities
+            .iter()
+            .filter(|(_, state)| state.dynamic.is_none())
+            .filter_map(|(entity_id, state)| {
+                let up = self.get_entity_controller(*entity_id).ok()?.up;
+                Some((*entity_id, gravity.dot(&up) * state.gravity_scale))
+            })
+            .collect();
+        for (entity_id, acceleration) in accelerations {
+            if let Some(state) = self.entities.get_mut(&entity_id) {
+                // Grounded entities keep a single step of pull towards the ground, however long they rest
+                let vertical_velocity = if state.grounded {
+                    0.0
+                } else {
+                    state.vertical_velocity
+                };
+                state.vertical_velocity = vertical_velocity + acceleration * dt;
+            }
+        }
+    }
+
+    /// Angle between the controller's up vector and the ground below `translation`, if there is any.
+    fn ground_angle(&self, entity_id: EntityId, translation: Vector<f32>) -> Option<f32> {
+        let up = self.get_entity_controller(entity_id).ok()?.up;
+        let entity_collider = self.get_entity_collider(entity_id).ok()?;
+        let half_extents = entity_collider.shape().compute_local_aabb().half_extents();
+        let max_toi = half_extents.dot(&up.abs()) + GROUND_PROBE_MARGIN;
+
+        let ray = Ray::new(Point3::from(translation), -*up);
+        let filter = QueryFilter::new()
+            .groups(self.get_entity_movement_groups(entity_id).ok()?)
+            .exclude_rigid_body(entity_id.body());
+        let (_, intersection) = self.query_pipeline.cast_ray_and_get_normal(
+            &self.bodies,
+            &self.colliders,
+            &ray,
+            max_toi,
+            true,
+            filter,
+        )?;
+        Some(up.angle(&intersection.normal))
+    }
+
+    /// Starts a jump with the given upward speed if the entity is grounded.
+    ///
+    /// Returns whether the entity actually jumped.
+    pub fn jump(&mut self, entity_id: EntityId, speed: f32) -> Result<bool, CollisionError> {
+        self.validate_entity(entity_id)?;
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        if !state.grounded {
+            return Ok(false);
         }
 
-        Movement {
-            next_position: calculated_movement.translation,
-            collisions: events,
+        state.vertical_velocity = speed;
+        state.grounded = false;
+        Ok(true)
+    }
+
+    /// Whether the entity stood on the ground after its last movement.
+    pub fn is_grounded(&self, entity_id: EntityId) -> Result<bool, CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.entities
+            .get(&entity_id)
+            .map(|state| state.grounded)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
+
+    /// Scales the world gravity for a single entity, `0.0` turns gravity off for it.
+    pub fn set_entity_gravity_scale(
+        &mut self,
+        entity_id: EntityId,
+        gravity_scale: f32,
+    ) -> Result<(), CollisionError> {
+        self.validate_entity(entity_id)?;
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        state.gravity_scale = gravity_scale;
+        if gravity_scale == 0.0 {
+            state.vertical_velocity = 0.0;
         }
+        // Rapier only applies the gravity to dynamic bodies, kinematic ones fall through the controller
+        self.get_mut_entity(entity_id)?
+            .set_gravity_scale(gravity_scale, true);
+        Ok(())
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vec<f32>) {
+        self.gravity = gravity;
+    }
+
+    pub fn gravity(&self) -> &Vec<f32> {
+        &self.gravity
     }
 
     pub fn update_entity_rotation(
         &mut self,
-        entity_handle: RigidBodyHandle,
+        entity_id: EntityId,
         i: f32,
         j: f32,
         k: f32,
         w: f32,
-    ) {
-        let new_rotation = Quaternion::new(w, i, j, k);
-        let entity = self.get_mut_entity(entity_handle);
-        let quaternion = UnitQuaternion::new_normalize(new_rotation);
+    ) -> Result<(), CollisionError> {
+        let quaternion = normalize_quaternion(i, j, k, w)?;
+        let entity = self.get_mut_entity(entity_id)?;
         entity.set_next_kinematic_rotation(quaternion);
+        Ok(())
     }
 
-    pub fn get_entity(&self, entity_handle: RigidBodyHandle) -> &RigidBody {
+    pub fn get_entity(&self, entity_id: EntityId) -> Result<&RigidBody, CollisionError> {
+        self.validate_entity(entity_id)?;
         self.bodies
-            .get(entity_handle)
-            .expect("entity did not exist in the RigidBodySet")
+            .get(entity_id.body())
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
     }
 
-    pub fn get_mut_entity(&mut self, entity_handle: RigidBodyHandle) -> &mut RigidBody {
+    pub fn get_mut_entity(
+        &mut self,
+        entity_id: EntityId,
+    ) -> Result<&mut RigidBody, CollisionError> {
+        self.validate_entity(entity_id)?;
         self.bodies
-            .get_mut(entity_handle)
-            .expect("entity did not exist in the RigidBodySet")
+            .get_mut(entity_id.body())
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
+
+    pub fn get_entity_collider(&self, entity_id: EntityId) -> Result<&Collider, CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.colliders
+            .get(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))
+    }
+
+    /// Makes sure both handles still exist and that the collider is attached to the body,
+    /// so late packets for unloaded entities can't panic or touch another entity.
+    fn validate_entity(&self, entity_id: EntityId) -> Result<(), CollisionError> {
+        if !self.bodies.contains(entity_id.body()) {
+            return Err(CollisionError::UnknownEntity(entity_id.body()));
+        }
+
+        let collider = self
+            .colliders
+            .get(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?;
+        if collider.parent() != Some(entity_id.body()) {
+            return Err(CollisionError::MismatchedHandles(
+                entity_id.body(),
+                entity_id.collider(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the controller used by entities without a profile.
+    pub fn set_controller_config(
+        &mut self,
+        config: &CharacterControllerConfig,
+    ) -> Result<(), CollisionError> {
+        self.controller = config.to_controller()?;
+        Ok(())
+    }
+
+    /// Adds or replaces a named controller profile, entities already using it are updated as well.
+    pub fn set_controller_profile(
+        &mut self,
+        name: String,
+        config: &CharacterControllerConfig,
+    ) -> Result<(), CollisionError> {
+        self.controller_profiles
+            .insert(name, config.to_controller()?);
+        Ok(())
+    }
+
+    /// Applies the default controller and every profile, e.g. from [`ControllerProfiles::from_file`].
+    pub fn load_controller_profiles(
+        &mut self,
+        profiles: &ControllerProfiles,
+    ) -> Result<(), CollisionError> {
+        self.set_controller_config(&profiles.default)?;
+        for (name, config) in profiles.profiles.iter() {
+            self.set_controller_profile(name.clone(), config)?;
+        }
+        Ok(())
+    }
+
+    /// Selects which controller profile moves the entity, `None` switches back to the default.
+    pub fn set_entity_controller_profile(
+        &mut self,
+        entity_id: EntityId,
+        profile: Option<String>,
+    ) -> Result<(), CollisionError> {
+        self.validate_entity(entity_id)?;
+        if let Some(name) = &profile {
+            if !self.controller_profiles.contains_key(name) {
+                return Err(CollisionError::UnknownControllerProfile(name.clone()));
+            }
+        }
+
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        state.controller_profile = profile;
+        Ok(())
+    }
+
+    fn get_entity_controller(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<&KinematicCharacterController, CollisionError> {
+        let state = self
+            .entities
+            .get(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        match &state.controller_profile {
+            Some(name) => self
+                .controller_profiles
+                .get(name)
+                .ok_or(CollisionError::UnknownControllerProfile(name.clone())),
+            None => Ok(&self.controller),
+        }
+    }
+
+    /// Returns the shape the entity was loaded with.
+    pub fn get_entity_shape(&self, entity_id: EntityId) -> Result<&EntityShape, CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.entities
+            .get(&entity_id)
+            .map(|state| &state.shape)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
+
+    pub fn get_entity_stance(&self, entity_id: EntityId) -> Result<Stance, CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.entities
+            .get(&entity_id)
+            .map(|state| state.stance)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
+
+    /// Resizes the entity (and its hitboxes) to the stance, keeping the bottom of its shape where it is.
+    ///
+    /// Returns `false` without changing anything if the entity would grow into the map or another
+    /// entity, e.g. standing up under a low ceiling.
+    pub fn set_entity_stance(
+        &mut self,
+        entity_id: EntityId,
+        stance: Stance,
+    ) -> Result<bool, CollisionError> {
+        if self.get_entity_stance(entity_id)? == stance {
+            return Ok(true);
+        }
+        // The headroom check has to see where everything is now, not after the last step
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+
+        let shape = self.entities[&entity_id]
+            .shape
+            .scaled_height(stance.height_scale())
+            .to_shared_shape()?;
+        let current = self
+            .get_entity_collider(entity_id)?
+            .shape()
+            .compute_local_aabb();
+        let resized = shape.compute_local_aabb();
+        // Keep the feet planted, the bottom of the shape stays at the same height
+        let mut position = *self.get_entity(entity_id)?.position();
+        position.translation.vector +=
+            position.rotation * Vector::new(0.0, current.mins.y - resized.mins.y, 0.0);
+
+        if resized.extents().y > current.extents().y {
+            let filter = QueryFilter::new()
+                .groups(self.get_entity_movement_groups(entity_id)?)
+                .exclude_rigid_body(entity_id.body())
+                .exclude_sensors();
+            let blocked = self.query_pipeline.intersection_with_shape(
+                &self.bodies,
+                &self.colliders,
+                &position,
+                shape.as_ref(),
+                filter,
+            );
+            if blocked.is_some() {
+                return Ok(false);
+            }
+        }
+
+        let body = self.get_mut_entity(entity_id)?;
+        body.set_position(position, true);
+        if body.is_kinematic() {
+            body.set_next_kinematic_position(position);
+        }
+        let collider = self
+            .colliders
+            .get_mut(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?;
+        collider.set_shape(shape);
+        collider.set_position(position);
+
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        state.stance = stance;
+        let hitboxes = state.hitboxes.clone();
+        if !hitboxes.is_empty() {
+            self.attach_hitboxes(entity_id, hitboxes)?;
+        }
+        self.place_hitboxes(entity_id, position);
+        // So hits follow the new shape right away
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+        Ok(true)
     }
 
-    pub fn load_entity(&mut self, spawn: Vec<f32>) -> (RigidBodyHandle, ColliderHandle) {
-        let entity_collider = ColliderBuilder::capsule_y(0.5, 0.2).build();
+    /// Loads an entity which is part of and collides with every layer.
+    pub fn load_entity(
+        &mut self,
+        spawn: Vec<f32>,
+        shape: EntityShape,
+    ) -> Result<EntityId, CollisionError> {
+        self.load_entity_with(EntityDescriptor::new(spawn, shape))
+    }
+
+    pub fn load_entity_with(
+        &mut self,
+        descriptor: EntityDescriptor,
+    ) -> Result<EntityId, CollisionError> {
+        let EntityDescriptor {
+            spawn,
+            shape,
+            layers,
+            tag,
+            dynamic,
+            hitboxes,
+        } = descriptor;
+        let mut entity_collider = ColliderBuilder::new(shape.to_shared_shape()?)
+            // Entities are kinematic, so they have to opt into events against each other and the map
+            .active_collision_types(ActiveCollisionTypes::all())
+            .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+            .collision_groups(layers.to_interaction_groups())
+            .user_data(tag.map_or(0, EntityTag::to_user_data));
+        let body = match dynamic {
+            Some(dynamic) => {
+                entity_collider = entity_collider
+                    .mass(dynamic.mass)
+                    .restitution(dynamic.restitution)
+                    .friction(dynamic.friction);
+                RigidBodyBuilder::dynamic()
+                    .linear_damping(dynamic.linear_damping)
+                    .angular_damping(dynamic.angular_damping)
+                    .ccd_enabled(dynamic.ccd)
+            }
+            None => RigidBodyBuilder::kinematic_position_based(),
+        };
         let handle = self.bodies.insert(
-            RigidBodyBuilder::kinematic_position_based()
-                .translation(spawn.into_rapier()) // Maybe add rotation in the future
+            body.translation(spawn.into_rapier()) // Maybe add rotation in the future
                 .enabled(true)
-                // .ccd_enabled(true)
                 .build(),
         );
-        let collider = self
-            .colliders
-            .insert_with_parent(entity_collider, handle, &mut self.bodies);
+        let collider =
+            self.colliders
+                .insert_with_parent(entity_collider.build(), handle, &mut self.bodies);
+
+        let entity_id = EntityId::new(handle, collider);
+        self.entities
+            .insert(entity_id, EntityState::new(shape, layers, tag, dynamic));
+        if !hitboxes.is_empty() {
+            self.attach_hitboxes(entity_id, hitboxes)?;
+        }
+        Ok(entity_id)
+    }
+
+    pub fn get_entity_layers(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<CollisionLayers, CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.entities
+            .get(&entity_id)
+            .map(|state| state.layers)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
+
+    /// Moves the entity to other layers, e.g. when a player switches teams.
+    pub fn set_entity_layers(
+        &mut self,
+        entity_id: EntityId,
+        layers: CollisionLayers,
+    ) -> Result<(), CollisionError> {
+        self.validate_entity(entity_id)?;
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        state.layers = layers;
+        let groups = Self::entity_groups(state);
+        self.colliders
+            .get_mut(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?
+            .set_collision_groups(groups);
+        for handle in self.hitbox_colliders(entity_id) {
+            self.colliders[handle]
+                .set_collision_groups(CollisionLayers::hitbox(layers).to_interaction_groups());
+        }
+        Ok(())
+    }
+
+    pub fn get_entity_tag(&self, entity_id: EntityId) -> Result<Option<EntityTag>, CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.entities
+            .get(&entity_id)
+            .map(|state| state.tag)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
+
+    pub fn set_entity_tag(
+        &mut self,
+        entity_id: EntityId,
+        tag: Option<EntityTag>,
+    ) -> Result<(), CollisionError> {
+        self.validate_entity(entity_id)?;
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        state.tag = tag;
+        let user_data = tag.map_or(0, EntityTag::to_user_data);
+        self.colliders
+            .get_mut(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?
+            .user_data = user_data;
+        for handle in self.hitbox_colliders(entity_id) {
+            self.colliders[handle].user_data = user_data;
+        }
+        Ok(())
+    }
+
+    /// Resolves any collider (entity, map mesh or trigger) to the game object it belongs to,
+    /// e.g. the handles of [`Movement::collisions`] or a [`QueryHit`].
+    pub fn collider_tag(&self, handle: ColliderHandle) -> Option<EntityTag> {
+        self.colliders
+            .get(handle)
+            .and_then(|collider| EntityTag::from_user_data(collider.user_data))
+    }
+
+    /// Groups of the entity's own collider. Projectiles hit the hitboxes of an entity which has some instead.
+    fn entity_groups(state: &EntityState) -> InteractionGroups {
+        if state.hitboxes.is_empty() {
+            state.layers.to_interaction_groups()
+        } else {
+            state
+                .layers
+                .without_filter(CollisionLayer::Projectile)
+                .to_interaction_groups()
+        }
+    }
+
+    /// Attaches hit volumes (e.g. [`Hitbox::humanoid`]) as sensors on the hitbox layer, replacing earlier ones.
+    ///
+    /// They follow the entity's position and rotation. Projectiles pass through the entity's own collider
+    /// and hit the hitboxes instead, which queries and events report as [`EventTarget::Hitbox`].
+    ///
+    /// The hitboxes are given for the standing entity, they are squashed along with it in other stances.
+    pub fn attach_hitboxes(
+        &mut self,
+        entity_id: EntityId,
+        hitboxes: Vec<Hitbox>,
+    ) -> Result<(), CollisionError> {
+        self.validate_entity(entity_id)?;
+        let state = self
+            .entities
+            .get(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        let groups = CollisionLayers::hitbox(state.layers).to_interaction_groups();
+        let user_data = state.tag.map_or(0, EntityTag::to_user_data);
+        let scale = state.stance.height_scale();
+        // Build every collider first, so an invalid shape keeps the old hitboxes
+        let colliders = hitboxes
+            .iter()
+            .map(|hitbox| hitbox.scaled_height(scale))
+            .map(|hitbox| {
+                let collider = ColliderBuilder::new(hitbox.shape.to_shared_shape()?)
+                    .translation(hitbox.offset.into())
+                    .sensor(true)
+                    .active_collision_types(ActiveCollisionTypes::all())
+                    .active_events(ActiveEvents::COLLISION_EVENTS)
+                    .collision_groups(groups)
+                    .user_data(user_data)
+                    .build();
+                Ok((collider, hitbox.part))
+            })
+            .collect::<Result<Vec<_>, CollisionError>>()?;
+
+        for handle in self.hitbox_colliders(entity_id) {
+            self.hitboxes.remove(&handle);
+            self.colliders
+                .remove(handle, &mut self.island_manager, &mut self.bodies, true);
+        }
+        for (collider, part) in colliders {
+            let handle =
+                self.colliders
+                    .insert_with_parent(collider, entity_id.body(), &mut self.bodies);
+            self.hitboxes.insert(handle, (entity_id, part));
+        }
+
+        let state = self
+            .entities
+            .get_mut(&entity_id)
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))?;
+        state.hitboxes = hitboxes;
+        let groups = Self::entity_groups(state);
+        self.colliders
+            .get_mut(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?
+            .set_collision_groups(groups);
+        Ok(())
+    }
+
+    pub fn get_entity_hitboxes(&self, entity_id: EntityId) -> Result<&[Hitbox], CollisionError> {
+        self.validate_entity(entity_id)?;
+        self.entities
+            .get(&entity_id)
+            .map(|state| state.hitboxes.as_slice())
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
+    }
 
-        (handle, collider)
+    fn hitbox_colliders(&self, entity_id: EntityId) -> Vec<ColliderHandle> {
+        self.hitboxes
+            .iter()
+            .filter(|(_, (owner, _))| *owner == entity_id)
+            .map(|(handle, _)| *handle)
+            .collect()
+    }
+
+    fn get_entity_movement_groups(
+        &self,
+        entity_id: EntityId,
+    ) -> Result<InteractionGroups, CollisionError> {
+        self.entities
+            .get(&entity_id)
+            .map(|state| state.layers.movement_groups())
+            .ok_or(CollisionError::UnknownEntity(entity_id.body()))
     }
 
-    pub fn unload_entity(&mut self, handle: RigidBodyHandle) {
+    pub fn unload_entity(&mut self, entity_id: EntityId) -> Result<(), CollisionError> {
+        self.validate_entity(entity_id)?;
         self.bodies.remove(
-            handle,
+            entity_id.body(),
             &mut self.island_manager,
             &mut self.colliders,
             &mut self.impulse_joint_set,
             &mut self.multibody_joint_set,
             true,
         );
+        // The hitbox colliders were removed with the body
+        self.hitboxes.retain(|_, (owner, _)| *owner != entity_id);
+        self.entities.remove(&entity_id);
+        Ok(())
     }
 
-    // "./data/environment.gltf"
-    pub fn load_collider(&mut self, map_path: String) {
-        // Hardcode to [0] since it will
-        // always be 1 big mesh
-        let mesh = &load_gltf_file(map_path.to_string())[0];
-        let sub_tri_meshes = mesh.mesh_collection();
-        for tri_mesh in sub_tri_meshes {
-            self.add_tri_mesh(tri_mesh.vertices, tri_mesh.indices);
-        }
+    /// Pushes a dynamic entity, e.g. to throw a grenade or knock over a prop.
+    pub fn apply_impulse(
+        &mut self,
+        entity_id: EntityId,
+        impulse: Vec<f32>,
+    ) -> Result<(), CollisionError> {
+        self.get_mut_dynamic_entity(entity_id)?
+            .apply_impulse(impulse.into_rapier(), true);
+        Ok(())
     }
 
-    pub fn add_tri_mesh(&mut self, in_vertices: Vec<[f32; 3]>, indices: Vec<[u32; 3]>) {
-        let center: Vec<f32> = vec![0.0, 0.0, 0.0];
+    pub fn apply_torque_impulse(
+        &mut self,
+        entity_id: EntityId,
+        torque_impulse: Vec<f32>,
+    ) -> Result<(), CollisionError> {
+        self.get_mut_dynamic_entity(entity_id)?
+            .apply_torque_impulse(torque_impulse.into_rapier(), true);
+        Ok(())
+    }
+
+    pub fn set_linear_velocity(
+        &mut self,
+        entity_id: EntityId,
+        velocity: Vec<f32>,
+    ) -> Result<(), CollisionError> {
+        self.get_mut_dynamic_entity(entity_id)?
+            .set_linvel(velocity.into_rapier(), true);
+        Ok(())
+    }
+
+    fn get_mut_dynamic_entity(
+        &mut self,
+        entity_id: EntityId,
+    ) -> Result<&mut RigidBody, CollisionError> {
+        let body = self.get_mut_entity(entity_id)?;
+        if !body.is_dynamic() {
+            return Err(CollisionError::NotDynamic(entity_id.body()));
+        }
+        Ok(body)
+    }
 
-        let vertices = in_vertices
+    /// Poses of the dynamic entities which are still moving, sleeping ones haven't changed since they fell asleep.
+    pub fn dynamic_transforms(&self) -> Vec<BodyTransform> {
+        self.entities
             .iter()
-            .map(|vec| Point3::new(vec[0], vec[1], vec[2]))
-            .collect();
-        let collider_body = RigidBodyBuilder::fixed().build();
-        let body_handle = self.bodies.insert(collider_body);
-        let collider = ColliderBuilder::trimesh(vertices, indices)
-            .translation(center.into_rapier())
+            .filter(|(_, state)| state.dynamic.is_some())
+            .filter_map(|(entity_id, _)| {
+                let body = self.bodies.get(entity_id.body())?;
+                if body.is_sleeping() {
+                    return None;
+                }
+                let position = body.position();
+                let rotation = position.rotation.coords;
+                Some(BodyTransform {
+                    entity_id: *entity_id,
+                    translation: position.translation.vector.into(),
+                    rotation: [rotation.x, rotation.y, rotation.z, rotation.w],
+                    linear_velocity: (*body.linvel()).into(),
+                })
+            })
+            .collect()
+    }
+
+    /// Adds a sensor volume which reports entities entering, staying in and leaving it from [`Self::run_step`].
+    pub fn add_trigger(
+        &mut self,
+        name: String,
+        position: Vec<f32>,
+        shape: EntityShape,
+    ) -> Result<TriggerId, CollisionError> {
+        let shape = shape.to_shared_shape()?;
+        Ok(self.insert_trigger(name, position.into_rapier(), shape))
+    }
+
+    fn insert_trigger(
+        &mut self,
+        name: String,
+        translation: Vector<f32>,
+        shape: SharedShape,
+    ) -> TriggerId {
+        let collider = ColliderBuilder::new(shape)
+            .translation(translation)
+            .sensor(true)
+            .active_collision_types(ActiveCollisionTypes::all())
+            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .collision_groups(CollisionLayers::trigger().to_interaction_groups())
             .build();
-        self.colliders
-            .insert_with_parent(collider, body_handle, &mut self.bodies);
+        let handle = self.colliders.insert(collider);
+        self.colliders[handle].user_data = TriggerId(handle).to_tag().to_user_data();
+        self.triggers.insert(handle, TriggerVolume::new(name));
+        TriggerId(handle)
     }
 
-    pub fn new(map_path: String) -> Self {
-        let mut collider = Self::default();
-        collider.load_collider(map_path);
-        collider
+    /// Turns every map mesh whose mesh or node name starts with `name_prefix` into a trigger shaped like
+    /// the convex hull of the mesh, placed with its node's transform like the map.
+    ///
+    /// Meshes named with [`TRIGGER_PREFIX`](crate::map::TRIGGER_PREFIX) already come with
+    /// [`Self::attach_map`], this is for other prefixes.
+    pub fn load_map_triggers(
+        &mut self,
+        map_path: String,
+        name_prefix: &str,
+    ) -> Result<Vec<TriggerId>, CollisionError> {
+        let mut triggers = Vec::new();
+        for mesh in stream_gltf_file(map_path) {
+            let Some(name) = trigger_name(&mesh, name_prefix) else {
+                continue;
+            };
+
+            triggers.push(self.add_trigger(name, vec![0.0, 0.0, 0.0], trigger_hull(&mesh))?);
+        }
+
+        Ok(triggers)
     }
 
-    pub fn run_step(&mut self) {
-        let hooks = ();
-        let events = (); // TODO: Create event-handler
-        self.physics_pipeline.step(
-            &self.gravity.into_rapier(),
-            &self.integration_parameters,
+    /// Removes the trigger, entities inside of it don't get an exit event.
+    pub fn remove_trigger(&mut self, trigger: TriggerId) -> Result<(), CollisionError> {
+        self.triggers
+            .remove(&trigger.collider())
+            .ok_or(CollisionError::UnknownTrigger(trigger))?;
+        self.colliders.remove(
+            trigger.collider(),
             &mut self.island_manager,
-            &mut self.broad_phase,
-            &mut self.narrow_phase,
             &mut self.bodies,
-            &mut self.colliders,
-            &mut self.impulse_joint_set,
-            &mut self.multibody_joint_set,
-            &mut self.ccd_solver,
-            Some(&mut self.query_pipeline),
-            &hooks,
-            &events,
+            false,
         );
+        Ok(())
     }
-}
 
-impl Default for GameCollider {
-    fn default() -> Self {
-        // TODO: Create custom controller for Scrape which will configure all of these by default
-        let mut controller = KinematicCharacterController::default();
-        controller.slide = true;
-        // Don’t allow climbing slopes larger than 45 degrees.
-        controller.max_slope_climb_angle = 60.0_f32.to_radians();
-        // Automatically slide down on slopes smaller than 30 degrees.
-        controller.min_slope_slide_angle = 30.0_f32.to_radians();
-        controller.offset = rapier3d::control::CharacterLength::Relative(0.01);
+    pub fn find_trigger(&self, name: &str) -> Option<TriggerId> {
+        self.triggers
+            .iter()
+            .find(|(_, volume)| volume.name == name)
+            .map(|(handle, _)| TriggerId(*handle))
+    }
 
-        Self {
-            bodies: RigidBodySet::new(),
-            colliders: ColliderSet::new(),
-            controller,
-            query_pipeline: QueryPipeline::new(),
-            physics_pipeline: PhysicsPipeline::new(),
+    pub fn trigger_name(&self, trigger: TriggerId) -> Option<&str> {
+        self.triggers
+            .get(&trigger.collider())
+            .map(|volume| volume.name.as_str())
+    }
 
-            integration_parameters: IntegrationParameters::default(),
-            island_manager: IslandManager::new(),
-            broad_phase: BroadPhase::new(),
-            narrow_phase: NarrowPhase::new(),
-            impulse_joint_set: ImpulseJointSet::new(),
-            multibody_joint_set: MultibodyJointSet::new(),
-            ccd_solver: CCDSolver::new(),
-            gravity: vec![0.0, 0.0, 0.0],
+    /// Entities which were inside the trigger after the last step.
+    pub fn trigger_occupants(&self, trigger: TriggerId) -> Option<&HashSet<EntityId>> {
+        self.triggers
+            .get(&trigger.collider())
+            .map(|volume| &volume.occupants)
+    }
+
+    /// Copies the state of every entity, cheap enough to take every tick for rollback or replays.
+    ///
+    /// Take it after [`Self::run_step`], pending kinematic movements aren't part of it.
+    pub fn snapshot(&self) -> WorldSnapshot {
+        let entities = self
+            .entities
+            .iter()
+            .filter_map(|(entity_id, state)| {
+                let body = self.bodies.get(entity_id.body())?;
+                let position = body.position();
+                let rotation = position.rotation.coords;
+                Some(EntitySnapshot {
+                    entity_id: *entity_id,
+                    translation: position.translation.vector.into(),
+                    rotation: [rotation.x, rotation.y, rotation.z, rotation.w],
+                    shape: state.shape.clone(),
+                    layers: state.layers,
+                    tag: state.tag,
+                    controller_profile: state.controller_profile.clone(),
+                    vertical_velocity: state.vertical_velocity,
+                    grounded: state.grounded,
+                    gravity_scale: state.gravity_scale,
+                    dynamic: state.dynamic,
+                    linear_velocity: (*body.linvel()).into(),
+                    angular_velocity: (*body.angvel()).into(),
+                    hitboxes: state.hitboxes.clone(),
+                    stance: state.stance,
+                })
+            })
+            .collect();
+
+        WorldSnapshot {
+            tick: self.tick,
+            gravity: self.gravity.clone(),
+            entities,
+            trigger_occupants: self
+                .triggers
+                .iter()
+                .map(|(handle, volume)| {
+                    (
+                        TriggerId(*handle),
+                        volume.occupants.iter().copied().collect(),
+                    )
+                })
+                .collect(),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::GameCollider;
+    /// Puts every entity back to the state of the snapshot.
+    ///
+    /// Entities loaded after the snapshot was taken are unloaded and entities unloaded since are
+    /// loaded again, which gives them a new id. Returns the id every entity of the snapshot has now.
+    ///
+    /// The tick goes back to the snapshot's as well, and the poses recorded after it are dropped
+    /// from the history, so the ticks simulated again replace them.
+    pub fn restore(
+        &mut self,
+        snapshot: &WorldSnapshot,
+    ) -> Result<HashMap<EntityId, EntityId>, CollisionError> {
+        let kept: HashSet<EntityId> = snapshot
+            .entities
+            .iter()
+            .map(|entity| entity.entity_id)
+            .collect();
+        let stale: Vec<EntityId> = self
+            .entities
+            .keys()
+            .filter(|entity_id| !kept.contains(entity_id))
+            .copied()
+            .collect();
+        for entity_id in stale {
+            self.unload_entity(entity_id)?;
+        }
 
-    #[test]
-    pub fn simple_out_of_bounds_test() {
-        let mut collider = GameCollider::new("./data/environment.gltf".to_string());
-        let (body_handle, collider_handle) = collider.load_entity(vec![11.0, 2.0, 1.0]);
+        let mut entity_ids = HashMap::new();
+        for entity in snapshot.entities.iter() {
+            let entity_id = self.restore_entity(entity)?;
+            entity_ids.insert(entity.entity_id, entity_id);
+        }
 
-        let movement = collider.calculate_movement(body_handle, vec![body_handle], vec![1.0, 2.0, 1.0]);
-        let entity = collider.get_mut_entity(body_handle);
-        entity.set_next_kinematic_translation(entity.translation() + movement.next_position);
-        collider.run_step();
+        for volume in self.triggers.values_mut() {
+            volume.occupants.clear();
+        }
+        for (trigger, occupants) in snapshot.trigger_occupants.iter() {
+            if let Some(volume) = self.triggers.get_mut(&trigger.collider()) {
+                volume.occupants = occupants
+                    .iter()
+                    .filter_map(|entity_id| entity_ids.get(entity_id))
+                    .copied()
+                    .collect();
+            }
+        }
 
-        let entity = collider.get_entity(body_handle);
-        println!("Position: {:?}", entity.translation());
+        self.gravity = snapshot.gravity.clone();
+        self.tick = snapshot.tick;
+        self.history.discard_after(snapshot.tick);
+        self.query_pipeline.update(&self.bodies, &self.colliders);
+        Ok(entity_ids)
+    }
+
+    fn restore_entity(&mut self, entity: &EntitySnapshot) -> Result<EntityId, CollisionError> {
+        let entity_id = match self.get_entity_shape(entity.entity_id) {
+            Ok(_) => entity.entity_id,
+            Err(_) => self.load_entity_with(EntityDescriptor {
+                tag: entity.tag,
+                dynamic: entity.dynamic,
+                hitboxes: entity.hitboxes.clone(),
+                ..EntityDescriptor::new(entity.translation.to_vec(), entity.shape.clone())
+                    .layers(entity.layers)
+            })?,
+        };
+
+        let [i, j, k, w] = entity.rotation;
+        let position = Isometry3::from_parts(
+            Translation3::new(
+                entity.translation[0],
+                entity.translation[1],
+                entity.translation[2],
+            ),
+            UnitQuaternion::new_normalize(Quaternion::new(w, i, j, k)),
+        );
+        if self.entities[&entity_id].dynamic != entity.dynamic {
+            self.set_body_dynamics(entity_id, entity.dynamic)?;
+        }
+        let body = self.get_mut_entity(entity_id)?;
+        body.set_position(position, true);
+        body.set_next_kinematic_position(position);
+        body.set_gravity_scale(entity.gravity_scale, true);
+        if body.is_dynamic() {
+            body.set_linvel(entity.linear_velocity.into(), true);
+            body.set_angvel(entity.angular_velocity.into(), true);
+        }
+
+        let shape_changed = self.get_entity_shape(entity_id)? != &entity.shape
+            || self.get_entity_stance(entity_id)? != entity.stance;
+        let collider = self
+            .colliders
+            .get_mut(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?;
+        if shape_changed {
+            collider.set_shape(
+                entity
+                    .shape
+                    .scaled_height(entity.stance.height_scale())
+                    .to_shared_shape()?,
+            );
+        }
+        collider.set_position(position);
+        collider.user_data = entity.tag.map_or(0, EntityTag::to_user_data);
+
+        let hitboxes_changed =
+            self.get_entity_hitboxes(entity_id)? != entity.hitboxes || shape_changed;
+        self.entities.insert(
+            entity_id,
+            EntityState {
+                shape: entity.shape.clone(),
+                layers: entity.layers,
+                tag: entity.tag,
+                dynamic: entity.dynamic,
+                hitboxes: entity.hitboxes.clone(),
+                stance: entity.stance,
+                controller_profile: entity.controller_profile.clone(),
+                vertical_velocity: entity.vertical_velocity,
+                grounded: entity.grounded,
+                gravity_scale: entity.gravity_scale,
+            },
+        );
+        // Attaching also updates the groups and tags of the entity's colliders
+        if hitboxes_changed {
+            self.attach_hitboxes(entity_id, entity.hitboxes.clone())?;
+        } else {
+            self.set_entity_layers(entity_id, entity.layers)?;
+            self.set_entity_tag(entity_id, entity.tag)?;
+        }
+        self.place_hitboxes(entity_id, position);
+        Ok(entity_id)
+    }
+
+    /// Moves the hitboxes along with a body which was teleported to `position`,
+    /// attached colliders only follow the body during a step.
+    fn place_hitboxes(&mut self, entity_id: EntityId, position: Isometry3<f32>) {
+        for handle in self.hitbox_colliders(entity_id) {
+            let collider = &mut self.colliders[handle];
+            let local = collider.position_wrt_parent().copied().unwrap_or_default();
+            collider.set_position(position * local);
+        }
+    }
+
+    /// Switches the entity between a dynamic and a kinematic body, with the material and damping
+    /// [`Self::load_entity_with`] would have given it.
+    fn set_body_dynamics(
+        &mut self,
+        entity_id: EntityId,
+        dynamic: Option<DynamicBody>,
+    ) -> Result<(), CollisionError> {
+        let body_type = match dynamic {
+            Some(_) => RigidBodyType::Dynamic,
+            None => RigidBodyType::KinematicPositionBased,
+        };
+        let body = self.get_mut_entity(entity_id)?;
+        // Position based bodies ignore new velocities, so leftover speed has to go before the switch
+        body.set_linvel(Vector::zeros(), true);
+        body.set_angvel(Vector::zeros(), true);
+        body.set_body_type(body_type, true);
+        let material = dynamic.unwrap_or_default();
+        let (linear_damping, angular_damping, ccd) = match dynamic {
+            Some(dynamic) => (dynamic.linear_damping, dynamic.angular_damping, dynamic.ccd),
+            None => (0.0, 0.0, false),
+        };
+        body.set_linear_damping(linear_damping);
+        body.set_angular_damping(angular_damping);
+        body.enable_ccd(ccd);
+
+        let collider = self
+            .colliders
+            .get_mut(entity_id.collider())
+            .ok_or(CollisionError::UnknownCollider(entity_id.collider()))?;
+        match dynamic {
+            Some(dynamic) => collider.set_mass(dynamic.mass),
+            None => collider.set_density(ColliderBuilder::default_density()),
+        }
+        collider.set_restitution(material.restitution);
+        collider.set_friction(material.friction);
+        Ok(())
+    }
+
+    // "./data/environment.gltf"
+    pub fn load_collider(&mut self, map_path: String) {
+        self.attach_map(&MapGeometry::load(map_path));
+    }
+
+    /// Adds colliders for the map's trimeshes and its trigger volumes, which stay shared with every
+    /// other world the map is attached to.
+    pub fn attach_map(&mut self, map: &MapGeometry) {
+        for piece in map.pieces.iter() {
+            let handle = self.add_map_shape(piece.shape.clone());
+            // Map colliders are tagged with the index of the mesh in the file
+            self.colliders[handle].user_data =
+                EntityTag::new(EntityKind::Map, piece.mesh_index as u64).to_user_data();
+            self.map_surfaces.insert(handle, piece.surface.clone());
+        }
+        for trigger in map.triggers.iter() {
+            self.insert_trigger(trigger.name.clone(), Vector::zeros(), trigger.shape.clone());
+        }
+    }
+
+    /// Maps a map collider and triangle index (e.g. from a `FeatureId::Face`) back to the mesh,
+    /// primitive, node, material and vertex data it was loaded from.
+    pub fn describe_hit(
+        &self,
+        collider_handle: ColliderHandle,
+        triangle: u32,
+    ) -> Option<HitDescription> {
+        self.map_surfaces
+            .get(&collider_handle)?
+            .describe(triangle, None)
+    }
+
+    /// Same as [`Self::describe_hit`], but finds the triangle closest to `point` and
+    /// interpolates the vertex channels there.
+    pub fn describe_hit_at(
+        &self,
+        collider_handle: ColliderHandle,
+        point: Point3<f32>,
+    ) -> Option<HitDescription> {
+        let (triangle, barycentric) = self.project_on_surface(collider_handle, point)?;
+        self.map_surfaces
+            .get(&collider_handle)?
+            .describe(triangle, Some(barycentric))
+    }
+
+    /// Interpolates the vertex colours and UV sets of a map trimesh at the point closest to `point`.
+    ///
+    /// Designers can paint per-vertex gameplay data (surface hardness, no-spawn zones, ...) which is
+    /// read back here, e.g. at the hit point of a collision. Returns `None` for colliders which are
+    /// not part of the map.
+    pub fn sample_surface(
+        &self,
+        collider_handle: ColliderHandle,
+        point: Point3<f32>,
+    ) -> Option<VertexSample> {
+        let (triangle, barycentric) = self.project_on_surface(collider_handle, point)?;
+        self.map_surfaces
+            .get(&collider_handle)?
+            .tri_mesh
+            .interpolate(triangle as usize, barycentric)
+    }
+
+    /// Casts a ray and returns the closest hit within `max_distance`, e.g. for hitscan weapons.
+    ///
+    /// Returns `None` for a zero `direction`. Like every query it sees the colliders as of the last
+    /// [`Self::run_step`], movement batch or [`Self::restore`], entities loaded or moved since then
+    /// show up after the next one.
+    pub fn raycast(
+        &self,
+        origin: Vec<f32>,
+        direction: Vec<f32>,
+        max_distance: f32,
+        filter: &HitFilter,
+    ) -> Option<QueryHit> {
+        let ray = Self::query_ray(origin, direction)?;
+        let predicate = |handle, _: &Collider| filter.accepts(handle, self.event_target(handle));
+        let (handle, intersection) = self.query_pipeline.cast_ray_and_get_normal(
+            &self.bodies,
+            &self.colliders,
+            &ray,
+            max_distance,
+            true,
+            filter.apply(QueryFilter::new().predicate(&predicate)),
+        )?;
+        Some(self.ray_hit(&ray, handle, intersection))
+    }
+
+    /// Every hit along the ray within `max_distance`, sorted from closest to farthest,
+    /// e.g. for weapons which penetrate entities.
+    pub fn raycast_all(
+        &self,
+        origin: Vec<f32>,
+        direction: Vec<f32>,
+        max_distance: f32,
+        filter: &HitFilter,
+    ) -> Vec<QueryHit> {
+        let Some(ray) = Self::query_ray(origin, direction) else {
+            return Vec::new();
+        };
+
+        let mut hits = Vec::new();
+        let predicate = |handle, _: &Collider| filter.accepts(handle, self.event_target(handle));
+        self.query_pipeline.intersections_with_ray(
+            &self.bodies,
+            &self.colliders,
+            &ray,
+            max_distance,
+            true,
+            filter.apply(QueryFilter::new().predicate(&predicate)),
+            |handle, intersection| {
+                hits.push(self.ray_hit(&ray, handle, intersection));
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Sweeps `shape` from `from` along `direction` and returns the first collider it touches within `max_distance`.
+    ///
+    /// Returns `Ok(None)` when nothing was hit or `direction` is zero.
+    pub fn shape_cast(
+        &self,
+        shape: &EntityShape,
+        from: Vec<f32>,
+        direction: Vec<f32>,
+        max_distance: f32,
+        filter: &HitFilter,
+    ) -> Result<Option<QueryHit>, CollisionError> {
+        let shape = shape.to_shared_shape()?;
+        let Some(direction) = direction.into_rapier().try_normalize(1.0e-6) else {
+            return Ok(None);
+        };
+
+        let predicate = |handle, _: &Collider| filter.accepts(handle, self.event_target(handle));
+        let hit = self.query_pipeline.cast_shape(
+            &self.bodies,
+            &self.colliders,
+            &Isometry3::translation(from[0], from[1], from[2]),
+            &direction,
+            shape.as_ref(),
+            max_distance,
+            true,
+            filter.apply(QueryFilter::new().predicate(&predicate)),
+        );
+
+        // Witness and normal 1 belong to the hit collider and are already in world space
+        Ok(hit.map(|(handle, toi)| QueryHit {
+            target: self.event_target(handle),
+            point: toi.witness1,
+            normal: *toi.normal1,
+            distance: toi.toi,
+            triangle: self.hit_triangle(handle, toi.witness1),
+        }))
+    }
+
+    /// Same as [`Self::raycast`], but entities are rewound to where they were at the end of `tick`,
+    /// e.g. the tick a client saw when it fired. The static map is hit at its only position.
+    ///
+    /// Entities which were unloaded since can't be hit anymore.
+    pub fn raycast_at_tick(
+        &self,
+        tick: u64,
+        origin: Vec<f32>,
+        direction: Vec<f32>,
+        max_distance: f32,
+        filter: &HitFilter,
+    ) -> Result<Option<QueryHit>, CollisionError> {
+        let rewound = self.rewound_entities(tick, filter)?;
+        let Some(ray) = Self::query_ray(origin.clone(), direction.clone()) else {
+            return Ok(None);
+        };
+
+        let mut closest = self.rewind_map_hit(filter, |map_filter| {
+            Ok(self.raycast(origin, direction, max_distance, map_filter))
+        })?;
+        for (target, shape, pose) in rewound {
+            let Some(intersection) = shape.cast_ray_and_get_normal(&pose, &ray, max_distance, true)
+            else {
+                continue;
+            };
+            if closest
+                .as_ref()
+                .is_some_and(|hit| hit.distance <= intersection.toi)
+            {
+                continue;
+            }
+
+            closest = Some(QueryHit {
+                target,
+                point: ray.point_at(intersection.toi),
+                normal: intersection.normal,
+                distance: intersection.toi,
+                triangle: None,
+            });
+        }
+
+        Ok(closest)
+    }
+
+    /// Same as [`Self::shape_cast`], but against entities rewound to the end of `tick`, see [`Self::raycast_at_tick`].
+    pub fn shape_cast_at_tick(
+        &self,
+        tick: u64,
+        shape: &EntityShape,
+        from: Vec<f32>,
+        direction: Vec<f32>,
+        max_distance: f32,
+        filter: &HitFilter,
+    ) -> Result<Option<QueryHit>, CollisionError> {
+        let rewound = self.rewound_entities(tick, filter)?;
+        let cast_shape = shape.to_shared_shape()?;
+        let Some(velocity) = direction.into_rapier().try_normalize(1.0e-6) else {
+            return Ok(None);
+        };
+        let start = Isometry3::translation(from[0], from[1], from[2]);
+
+        let mut closest = self.rewind_map_hit(filter, |map_filter| {
+            self.shape_cast(shape, from, direction, max_distance, map_filter)
+        })?;
+        for (target, shape, pose) in rewound {
+            // The entity is the first shape, so witness and normal 1 are on the entity
+            let Ok(Some(toi)) = time_of_impact(
+                &pose,
+                &Vector::zeros(),
+                shape.as_ref(),
+                &start,
+                &velocity,
+                cast_shape.as_ref(),
+                max_distance,
+                true,
+            ) else {
+                continue;
+            };
+            if closest.as_ref().is_some_and(|hit| hit.distance <= toi.toi) {
+                continue;
+            }
+
+            closest = Some(QueryHit {
+                target,
+                point: pose * toi.witness1,
+                normal: pose * *toi.normal1,
+                distance: toi.toi,
+                triangle: None,
+            });
+        }
+
+        Ok(closest)
+    }
+
+    /// Entities the filter accepts, with their shapes and poses at the end of `tick`.
+    ///
+    /// Entities which changed stance since are rewound with the shape and hitboxes of the stance they had.
+    fn rewound_entities(
+        &self,
+        tick: u64,
+        filter: &HitFilter,
+    ) -> Result<Vec<RewoundEntity>, CollisionError> {
+        let poses = self
+            .history
+            .at(tick)
+            .ok_or(CollisionError::UnknownTick(tick))?;
+        let accepts = |handle: ColliderHandle, target: EventTarget| {
+            self.colliders.get(handle).is_some_and(|collider| {
+                filter.accepts(handle, target)
+                    && filter.layers.is_none_or(|layers| {
+                        layers
+                            .to_interaction_groups()
+                            .test(collider.collision_groups())
+                    })
+            })
+        };
+
+        let mut rewound = Vec::new();
+        for (entity_id, recorded) in poses.iter() {
+            let Some(state) = self.entities.get(entity_id) else {
+                continue;
+            };
+            let scale = recorded.stance.height_scale();
+            let resized = recorded.stance != state.stance;
+
+            let target = EventTarget::Entity(*entity_id);
+            if accepts(entity_id.collider(), target) {
+                let shape = if resized {
+                    state.shape.scaled_height(scale).to_shared_shape()?
+                } else {
+                    self.colliders[entity_id.collider()].shared_shape().clone()
+                };
+                rewound.push((target, shape, recorded.pose));
+            }
+
+            for handle in self.hitbox_colliders(*entity_id) {
+                let (_, part) = self.hitboxes[&handle];
+                let target = EventTarget::Hitbox {
+                    entity: *entity_id,
+                    part,
+                };
+                if !accepts(handle, target) {
+                    continue;
+                }
+
+                let collider = &self.colliders[handle];
+                let hitbox = state.hitboxes.iter().find(|hitbox| hitbox.part == part);
+                let (shape, local) = match hitbox {
+                    Some(hitbox) if resized => {
+                        let hitbox = hitbox.scaled_height(scale);
+                        let [x, y, z] = hitbox.offset;
+                        (
+                            hitbox.shape.to_shared_shape()?,
+                            Isometry3::translation(x, y, z),
+                        )
+                    }
+                    _ => (
+                        collider.shared_shape().clone(),
+                        collider.position_wrt_parent().copied().unwrap_or_default(),
+                    ),
+                };
+                rewound.push((target, shape, recorded.pose * local));
+            }
+        }
+
+        Ok(rewound)
+    }
+
+    /// Runs `query` against the map only, if the filter allows hitting it.
+    fn rewind_map_hit(
+        &self,
+        filter: &HitFilter,
+        query: impl FnOnce(&HitFilter) -> Result<Option<QueryHit>, CollisionError>,
+    ) -> Result<Option<QueryHit>, CollisionError> {
+        if !filter.hit_map {
+            return Ok(None);
+        }
+
+        let map_filter = HitFilter {
+            hit_entities: false,
+            hit_triggers: false,
+            ..filter.clone()
+        };
+        query(&map_filter)
+    }
+
+    /// Entities on layers `layers` interacts with whose collider is within `radius` of `center`,
+    /// closest first, e.g. everyone an explosion reaches.
+    ///
+    /// Walls don't block it, check [`Self::line_of_sight`] for each hit where they should.
+    pub fn overlap_sphere(
+        &self,
+        center: Vec<f32>,
+        radius: f32,
+        layers: CollisionLayers,
+    ) -> Vec<AreaHit> {
+        let center = Point3::from(center.into_rapier());
+        let sphere = Ball::new(radius);
+        let filter = HitFilter::entities_only().layers(layers);
+        let predicate = |handle, _: &Collider| filter.accepts(handle, self.event_target(handle));
+
+        let mut hits: HashMap<EntityId, AreaHit> = HashMap::new();
+        self.query_pipeline.intersections_with_shape(
+            &self.bodies,
+            &self.colliders,
+            &Isometry3::from(center),
+            &sphere,
+            filter.apply(QueryFilter::new().predicate(&predicate)),
+            |handle| {
+                // An entity with hitboxes is only reached through them, the closest one counts
+                let Some(entity_id) = self.event_target(handle).entity() else {
+                    return true;
+                };
+                let collider = &self.colliders[handle];
+                let projection = collider
+                    .shape()
+                    .project_point(collider.position(), &center, true);
+                let distance = (projection.point - center).norm();
+                if hits
+                    .get(&entity_id)
+                    .is_some_and(|hit| hit.distance <= distance)
+                {
+                    return true;
+                }
+
+                let entity_center = self.bodies[entity_id.body()].translation();
+                let direction = (entity_center - center.coords)
+                    .try_normalize(1.0e-6)
+                    .unwrap_or_else(Vector::zeros);
+                hits.insert(
+                    entity_id,
+                    AreaHit {
+                        entity_id,
+                        point: projection.point,
+                        distance,
+                        direction,
+                    },
+                );
+                true
+            },
+        );
+
+        let mut hits: Vec<AreaHit> = hits.into_values().collect();
+        hits.sort_by(|first, second| first.distance.total_cmp(&second.distance));
+        hits
+    }
+
+    /// Whether no part of the map is between the two points, e.g. an explosion and an [`AreaHit::point`].
+    pub fn line_of_sight(&self, from: Vec<f32>, to: Vec<f32>) -> bool {
+        let distance = (to.into_rapier() - from.into_rapier()).norm();
+        let direction = (to.into_rapier() - from.into_rapier()).as_slice().to_vec();
+        self.raycast(from, direction, distance, &HitFilter::map_only())
+            .is_none()
+    }
+
+    fn query_ray(origin: Vec<f32>, direction: Vec<f32>) -> Option<Ray> {
+        let direction = direction.into_rapier().try_normalize(1.0e-6)?;
+        Some(Ray::new(Point3::from(origin.into_rapier()), direction))
+    }
+
+    fn ray_hit(
+        &self,
+        ray: &Ray,
+        handle: ColliderHandle,
+        intersection: RayIntersection,
+    ) -> QueryHit {
+        let point = ray.point_at(intersection.toi);
+        QueryHit {
+            target: self.event_target(handle),
+            point,
+            normal: intersection.normal,
+            distance: intersection.toi,
+            triangle: self.hit_triangle(handle, point),
+        }
+    }
+
+    fn hit_triangle(&self, handle: ColliderHandle, point: Point3<f32>) -> Option<u32> {
+        if !self.map_surfaces.contains_key(&handle) {
+            return None;
+        }
+
+        self.project_on_surface(handle, point)
+            .map(|(triangle, _)| triangle)
+    }
+
+    /// Projects `point` onto a map trimesh, returning the closest triangle and the
+    /// barycentric coordinates of the projection.
+    fn project_on_surface(
+        &self,
+        collider_handle: ColliderHandle,
+        point: Point3<f32>,
+    ) -> Option<(u32, [f32; 3])> {
+        let collider = self.colliders.get(collider_handle)?;
+        let trimesh = collider.shape().as_trimesh()?;
+
+        let (_, (triangle, location)) =
+            trimesh.project_point_and_get_location(collider.position(), &point, false);
+        Some((triangle, location.barycentric_coordinates()?))
+    }
+
+    pub fn add_tri_mesh(
+        &mut self,
+        vertices: Vec<Point3<f32>>,
+        indices: Vec<[u32; 3]>,
+    ) -> ColliderHandle {
+        self.add_map_shape(SharedShape::trimesh(vertices, indices))
+    }
+
+    fn add_map_shape(&mut self, shape: SharedShape) -> ColliderHandle {
+        let center: Vec<f32> = vec![0.0, 0.0, 0.0];
+
+        let collider_body = RigidBodyBuilder::fixed().build();
+        let body_handle = self.bodies.insert(collider_body);
+        let collider = ColliderBuilder::new(shape)
+            .translation(center.into_rapier())
+            .collision_groups(CollisionLayers::world().to_interaction_groups())
+            .build();
+        self.colliders
+            .insert_with_parent(collider, body_handle, &mut self.bodies)
+    }
+
+    pub fn new(map_path: String) -> Self {
+        Self::with_map(&MapGeometry::load(map_path))
+    }
+
+    /// A new world on an already loaded map, e.g. one more match on the same map.
+    pub fn with_map(map: &MapGeometry) -> Self {
+        let mut collider = Self::default();
+        collider.attach_map(map);
+        collider
+    }
+
+    /// Advances the physics world by one step and returns the collision events it produced.
+    pub fn run_step(&mut self) -> Vec<PhysicsEvent> {
+        let hooks = ();
+        let events = StepEvents::new();
+        self.physics_pipeline.step(
+            &self.gravity.into_rapier(),
+            &self.integration_parameters,
+            &mut self.island_manager,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
+            &mut self.ccd_solver,
+            Some(&mut self.query_pipeline),
+            &hooks,
+            &events.collector,
+        );
+
+        let mut physics_events = events.drain(|handle| self.event_target(handle));
+        let trigger_events = self.update_triggers(&physics_events);
+        physics_events.extend(trigger_events);
+        self.apply_gravity();
+
+        self.tick += 1;
+        let poses = self
+            .entities
+            .iter()
+            .filter_map(|(entity_id, state)| {
+                let body = self.bodies.get(entity_id.body())?;
+                let pose = RecordedPose {
+                    pose: *body.position(),
+                    stance: state.stance,
+                };
+                Some((*entity_id, pose))
+            })
+            .collect();
+        self.history.record(self.tick, poses);
+
+        physics_events
+    }
+
+    /// Adds the wall-clock time since the last call and returns how many fixed steps are due.
+    ///
+    /// For each step the game resolves its movements (moving [`Self::fixed_dt`] worth of distance)
+    /// and calls [`Self::run_step`], so the simulation doesn't depend on how evenly the server ticks.
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.clock.advance(elapsed)
+    }
+
+    /// Changes how many steps per second [`Self::advance`] hands out, and the length rapier integrates each step with.
+    pub fn set_tick_rate(
+        &mut self,
+        tick_rate: u32,
+        max_substeps: u32,
+    ) -> Result<(), CollisionError> {
+        self.clock = FixedClock::new(tick_rate, max_substeps)?;
+        self.integration_parameters.dt = self.clock.dt().as_secs_f32();
+        Ok(())
+    }
+
+    /// Length of a step in seconds.
+    pub fn fixed_dt(&self) -> f32 {
+        self.integration_parameters.dt
+    }
+
+    pub fn clock(&self) -> &FixedClock {
+        &self.clock
+    }
+
+    /// Number of steps run so far, the entity poses after each one are kept in the transform history.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Oldest tick which can still be queried with [`Self::raycast_at_tick`].
+    pub fn oldest_history_tick(&self) -> Option<u64> {
+        self.history.oldest_tick()
+    }
+
+    /// How many ticks of entity poses are kept for [`Self::raycast_at_tick`] and [`Self::shape_cast_at_tick`].
+    pub fn set_history_length(&mut self, length: usize) {
+        self.history.set_length(length);
+    }
+
+    /// Tracks which entities are inside of which trigger and turns the sensor intersections
+    /// of a step into enter, stay and exit events.
+    fn update_triggers(&mut self, events: &[PhysicsEvent]) -> Vec<PhysicsEvent> {
+        let mut trigger_events = Vec::new();
+        let mut entered = HashSet::new();
+
+        for event in events {
+            let (first, second, started) = match event {
+                PhysicsEvent::IntersectionStarted { first, second } => (*first, *second, true),
+                PhysicsEvent::IntersectionStopped { first, second, .. } => (*first, *second, false),
+                _ => continue,
+            };
+            let (trigger, other) = match (first, second) {
+                (EventTarget::Trigger(trigger), other) | (other, EventTarget::Trigger(trigger)) => {
+                    (trigger, other)
+                }
+                _ => continue,
+            };
+            let Some(volume) = self.triggers.get_mut(&trigger.collider()) else {
+                continue;
+            };
+
+            match (started, other) {
+                (true, EventTarget::Entity(entity)) if volume.occupants.insert(entity) => {
+                    entered.insert((trigger, entity));
+                    trigger_events.push(PhysicsEvent::TriggerEntered { trigger, entity });
+                }
+                (false, EventTarget::Entity(entity)) if volume.occupants.remove(&entity) => {
+                    trigger_events.push(PhysicsEvent::TriggerExited { trigger, entity });
+                }
+                // The entity was unloaded, so its collider doesn't resolve to it anymore
+                (false, EventTarget::Collider(handle)) => {
+                    if let Some(entity) = volume.remove_collider(handle) {
+                        trigger_events.push(PhysicsEvent::TriggerExited { trigger, entity });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (handle, volume) in self.triggers.iter() {
+            let trigger = TriggerId(*handle);
+            for entity in volume.occupants.iter() {
+                if !entered.contains(&(trigger, *entity)) {
+                    trigger_events.push(PhysicsEvent::TriggerStayed {
+                        trigger,
+                        entity: *entity,
+                    });
+                }
+            }
+        }
+
+        trigger_events
+    }
+
+    fn event_target(&self, handle: ColliderHandle) -> EventTarget {
+        if self.map_surfaces.contains_key(&handle) {
+            return EventTarget::Map(handle);
+        }
+        if self.triggers.contains_key(&handle) {
+            return EventTarget::Trigger(TriggerId(handle));
+        }
+        if let Some((entity, part)) = self.hitboxes.get(&handle) {
+            return EventTarget::Hitbox {
+                entity: *entity,
+                part: *part,
+            };
+        }
+
+        self.colliders
+            .get(handle)
+            .and_then(|collider| collider.parent())
+            .map(|body| EntityId::new(body, handle))
+            .filter(|entity_id| self.entities.contains_key(entity_id))
+            .map_or(EventTarget::Collider(handle), EventTarget::Entity)
+    }
+}
+
+impl Default for GameCollider {
+    fn default() -> Self {
+        let controller = CharacterControllerConfig::default()
+            .to_controller()
+            .expect("default controller config should be valid");
+
+        Self {
+            bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            controller,
+            controller_profiles: HashMap::new(),
+            query_pipeline: QueryPipeline::new(),
+            physics_pipeline: PhysicsPipeline::new(),
+            map_surfaces: HashMap::new(),
+            entities: HashMap::new(),
+            triggers: HashMap::new(),
+            hitboxes: HashMap::new(),
+            tick: 0,
+            history: TransformHistory::new(DEFAULT_HISTORY_LENGTH),
+            clock: FixedClock::default(),
+            gravity: vec![0.0, -9.81, 0.0],
+
+            integration_parameters: IntegrationParameters {
+                dt: FixedClock::default().dt().as_secs_f32(),
+                ..IntegrationParameters::default()
+            },
+            island_manager: IslandManager::new(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            impulse_joint_set: ImpulseJointSet::new(),
+            multibody_joint_set: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameCollider, MovementRequest, Point3};
+    use crate::config::CharacterControllerConfig;
+    use crate::entity::{DynamicBody, EntityDescriptor, EntityKind, EntityTag};
+    use crate::errors::CollisionError;
+    use crate::events::{EventTarget, PhysicsEvent};
+    use crate::hitbox::{Hitbox, HitboxPart};
+    use crate::layers::CollisionLayers;
+    use crate::map::MapGeometry;
+    use crate::queries::HitFilter;
+    use crate::shape::EntityShape;
+    use crate::snapshot::WorldSnapshot;
+    use crate::stance::Stance;
+    use crate::triggers::TriggerId;
+    use rapier3d::prelude::Vector;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    pub fn simple_out_of_bounds_test() {
+        let mut collider = GameCollider::new("./data/environment.gltf".to_string());
+        let entity_id = collider
+            .load_entity(vec![11.0, 2.0, 1.0], EntityShape::default())
+            .unwrap();
+
+        let movement = collider
+            .calculate_movement(entity_id, vec![entity_id.collider()], vec![1.0, 2.0, 1.0])
+            .unwrap();
+        let entity = collider.get_mut_entity(entity_id).unwrap();
+        entity.set_next_kinematic_translation(entity.translation() + movement.next_position);
+        collider.run_step();
+
+        let entity = collider.get_entity(entity_id).unwrap();
+        println!("Position: {:?}", entity.translation());
+    }
+
+    #[test]
+    pub fn stale_entity_test() {
+        let mut collider = GameCollider::default();
+        let entity_id = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+        let other_id = collider
+            .load_entity(vec![5.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+        collider.unload_entity(entity_id).unwrap();
+
+        assert_eq!(
+            collider
+                .calculate_movement(entity_id, Vec::new(), vec![1.0, 0.0, 0.0])
+                .err(),
+            Some(CollisionError::UnknownEntity(entity_id.body()))
+        );
+        assert!(collider
+            .update_entity_rotation(entity_id, 0.0, 0.0, 0.0, 1.0)
+            .is_err());
+        assert!(collider.unload_entity(entity_id).is_err());
+        assert!(collider.get_entity(other_id).is_ok());
+    }
+
+    #[test]
+    pub fn sample_surface_test() {
+        let collider = GameCollider::new("./data/environment.gltf".to_string());
+        let (handle, surface) = collider
+            .map_surfaces
+            .iter()
+            .find(|(_, surface)| !surface.tri_mesh.indices.is_empty())
+            .unwrap();
+        let surface = &surface.tri_mesh;
+
+        // The collider is in world space, the node transform moved it away from the file's positions
+        let trimesh = collider.colliders[*handle].shape().as_trimesh().unwrap();
+        let corners = surface.indices[0].map(|index| trimesh.vertices()[index as usize]);
+        let centroid =
+            Point3::from((corners[0].coords + corners[1].coords + corners[2].coords) / 3.0);
+        assert_ne!(
+            Point3::from(surface.vertices[surface.indices[0][0] as usize]),
+            corners[0]
+        );
+
+        let sample = collider.sample_surface(*handle, centroid).unwrap();
+        assert!(sample.uvs.contains_key(&0));
+
+        let description = collider.describe_hit_at(*handle, centroid).unwrap();
+        assert_eq!(description.triangle, 0);
+        assert_eq!(description.sample, Some(sample));
+
+        let triangle_count = surface.indices.len() as u32;
+        let backface = collider.describe_hit(*handle, triangle_count).unwrap();
+        assert_eq!(backface.triangle, 0);
+        assert_eq!(backface.source.mesh_index, 0);
+    }
+
+    #[test]
+    pub fn entity_shape_test() {
+        let mut collider = GameCollider::default();
+        let bullet = EntityShape::Ball { radius: 0.05 };
+        let bullet_id = collider
+            .load_entity(vec![0.0, 0.0, 0.0], bullet.clone())
+            .unwrap();
+        collider
+            .load_entity(
+                vec![1.0, 0.0, 0.0],
+                EntityShape::Cuboid {
+                    half_extents: [0.1, 1.0, 1.0],
+                },
+            )
+            .unwrap();
+
+        assert_eq!(collider.get_entity_shape(bullet_id), Ok(&bullet));
+
+        let movement = collider
+            .calculate_movement(bullet_id, Vec::new(), vec![2.0, 0.0, 0.0])
+            .unwrap();
+        // The sweep has to use the small ball, a player capsule would stop at 0.7
+        assert!(movement.next_position.x > 0.8 && movement.next_position.x < 0.86);
+    }
+
+    #[test]
+    pub fn controller_profile_test() {
+        let mut collider = GameCollider::default();
+        let entity_id = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+
+        assert_eq!(
+            collider.set_entity_controller_profile(entity_id, Some("bullet".to_string())),
+            Err(CollisionError::UnknownControllerProfile(
+                "bullet".to_string()
+            ))
+        );
+
+        let bullet = CharacterControllerConfig {
+            slide: false,
+            ..Default::default()
+        };
+        collider
+            .set_controller_profile("bullet".to_string(), &bullet)
+            .unwrap();
+        collider
+            .set_entity_controller_profile(entity_id, Some("bullet".to_string()))
+            .unwrap();
+        assert!(!collider.get_entity_controller(entity_id).unwrap().slide);
+
+        collider
+            .set_entity_controller_profile(entity_id, None)
+            .unwrap();
+        assert!(collider.get_entity_controller(entity_id).unwrap().slide);
+    }
+
+    #[test]
+    pub fn gravity_and_jump_test() {
+        let mut collider = GameCollider::default();
+        collider
+            .load_entity(
+                vec![0.0, 0.0, 0.0],
+                EntityShape::Cuboid {
+                    half_extents: [5.0, 0.1, 5.0],
+                },
+            )
+            .unwrap();
+        let entity_id = collider
+            .load_entity(vec![0.0, 1.5, 0.0], EntityShape::default())
+            .unwrap();
+
+        let step = |collider: &mut GameCollider| {
+            let movement = collider
+                .calculate_movement(entity_id, Vec::new(), vec![0.0, 0.0, 0.0])
+                .unwrap();
+            let entity = collider.get_mut_entity(entity_id).unwrap();
+            entity.set_next_kinematic_translation(entity.translation() + movement.next_position);
+            collider.run_step();
+            movement
+        };
+
+        assert!(!step(&mut collider).grounded);
+        let landed = (0..120).any(|_| step(&mut collider).grounded);
+        assert!(landed);
+        let resting_height = collider.get_entity(entity_id).unwrap().translation().y;
+        assert!(resting_height > 0.75 && resting_height < 0.85);
+        assert!(!step(&mut collider).is_sliding_down_slope);
+
+        assert_eq!(collider.jump(entity_id, 5.0), Ok(true));
+        step(&mut collider);
+        assert!(collider.get_entity(entity_id).unwrap().translation().y > resting_height);
+        assert_eq!(collider.jump(entity_id, 5.0), Ok(false));
+
+        // Resolving more movements in a step doesn't make the entity fall faster
+        let vertical_velocity = |collider: &GameCollider| {
+            collider
+                .snapshot()
+                .entities
+                .into_iter()
+                .find(|entity| entity.entity_id == entity_id)
+                .unwrap()
+                .vertical_velocity
+        };
+        let before = vertical_velocity(&collider);
+        for _ in 0..3 {
+            collider
+                .calculate_movement(entity_id, Vec::new(), vec![0.0, 0.0, 0.0])
+                .unwrap();
+        }
+        collider.run_step();
+        let after = vertical_velocity(&collider);
+        assert!((before - after - 9.81 * collider.fixed_dt()).abs() < 1.0e-4);
+    }
+
+    #[test]
+    pub fn ceiling_bump_test() {
+        let mut collider = GameCollider::default();
+        let cuboid = EntityShape::Cuboid {
+            half_extents: [5.0, 0.1, 5.0],
+        };
+        collider
+            .load_entity(vec![0.0, 0.0, 0.0], cuboid.clone())
+            .unwrap();
+        collider.load_entity(vec![0.0, 2.2, 0.0], cuboid).unwrap();
+        let entity_id = collider
+            .load_entity(vec![0.0, 0.8, 0.0], EntityShape::default())
+            .unwrap();
+        collider.run_step();
+
+        let step = |collider: &mut GameCollider| {
+            let movement = collider
+                .calculate_movement(entity_id, Vec::new(), vec![0.0, 0.0, 0.0])
+                .unwrap();
+            let entity = collider.get_mut_entity(entity_id).unwrap();
+            entity.set_next_kinematic_translation(entity.translation() + movement.next_position);
+            collider.run_step();
+            movement
+        };
+        step(&mut collider);
+        assert_eq!(collider.jump(entity_id, 10.0), Ok(true));
+        // The jump reaches the ceiling about 0.6 units up within a few steps
+        let bumped = (0..10).any(|_| {
+            step(&mut collider)
+                .collisions
+                .iter()
+                .any(|collision| collision.toi.normal1.y < 0.0)
+        });
+        assert!(bumped);
+
+        let state = collider
+            .snapshot()
+            .entities
+            .into_iter()
+            .find(|entity| entity.entity_id == entity_id)
+            .unwrap();
+        assert!(state.vertical_velocity <= 0.0);
+        assert!(state.translation[1] < 1.4);
+    }
+
+    #[test]
+    pub fn physics_events_test() {
+        let mut collider = GameCollider::default();
+        let first = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        let second = collider
+            .load_entity(vec![0.5, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+
+        let events = collider.run_step();
+        assert!(events.iter().any(|event| {
+            matches!(event, PhysicsEvent::ContactStarted { .. })
+                && event.involves(first)
+                && event.involves(second)
+        }));
+
+        collider.unload_entity(second).unwrap();
+        let events = collider.run_step();
+        assert!(events.iter().any(|event| {
+            matches!(event, PhysicsEvent::ContactStopped { removed: true, .. })
+                && event.involves(first)
+        }));
+        assert!(collider.run_step().is_empty());
+    }
+
+    #[test]
+    pub fn raycast_test() {
+        let mut collider = GameCollider::default();
+        let shooter = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+        let near = collider
+            .load_entity(vec![3.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        let far = collider
+            .load_entity(vec![6.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        collider.run_step();
+
+        let filter = HitFilter::default().exclude_entity(shooter);
+        let hit = collider
+            .raycast(vec![0.0, 0.0, 0.0], vec![1.0, 0.0, 0.0], 10.0, &filter)
+            .unwrap();
+        assert_eq!(hit.target, EventTarget::Entity(near));
+        assert!((hit.distance - 2.5).abs() < 1.0e-4);
+        assert!((hit.normal.x + 1.0).abs() < 1.0e-4);
+        assert_eq!(hit.triangle, None);
+
+        let hits = collider.raycast_all(vec![0.0, 0.0, 0.0], vec![1.0, 0.0, 0.0], 10.0, &filter);
+        let targets: Vec<_> = hits.iter().map(|hit| hit.target).collect();
+        assert_eq!(
+            targets,
+            vec![EventTarget::Entity(near), EventTarget::Entity(far)]
+        );
+
+        let filter = filter.exclude_entity(near);
+        let hit = collider
+            .shape_cast(
+                &EntityShape::Ball { radius: 0.1 },
+                vec![0.0, 0.0, 0.0],
+                vec![1.0, 0.0, 0.0],
+                10.0,
+                &filter,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.target, EventTarget::Entity(far));
+        assert!((hit.distance - 5.4).abs() < 1.0e-3);
+        assert!((hit.point.x - 5.5).abs() < 1.0e-3);
+
+        assert!(collider
+            .raycast(vec![0.0, 0.0, 0.0], vec![0.0, 0.0, 0.0], 10.0, &filter)
+            .is_none());
+        assert!(collider
+            .raycast(
+                vec![0.0, 0.0, 0.0],
+                vec![1.0, 0.0, 0.0],
+                10.0,
+                &HitFilter::map_only()
+            )
+            .is_none());
+    }
+
+    #[test]
+    pub fn batch_movement_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        let first = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        let second = collider
+            .load_entity(vec![0.0, 0.0, 5.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        let wall = collider
+            .load_entity(
+                vec![2.0, 0.0, 5.0],
+                EntityShape::Cuboid {
+                    half_extents: [0.5, 2.0, 2.0],
+                },
+            )
+            .unwrap();
+        collider.unload_entity(wall).unwrap();
+        let blocker = collider
+            .load_entity(
+                vec![2.0, 0.0, 5.0],
+                EntityShape::Cuboid {
+                    half_extents: [0.5, 2.0, 2.0],
+                },
+            )
+            .unwrap();
+
+        let request = |entity_id| MovementRequest {
+            entity_id,
+            exclude_colliders: Vec::new(),
+            desired: vec![3.0, 0.0, 0.0],
+        };
+        let movements =
+            collider.calculate_movements(vec![request(first), request(wall), request(second)]);
+
+        assert_eq!(movements.len(), 3);
+        let first_movement = movements[0].as_ref().unwrap();
+        assert!((first_movement.next_position.x - 3.0).abs() < 1.0e-3);
+        assert!(first_movement.collisions.is_empty());
+        assert!(movements[1].is_err());
+        let second_movement = movements[2].as_ref().unwrap();
+        assert!(second_movement.next_position.x < 1.0);
+        assert!(second_movement
+            .collisions
+            .iter()
+            .any(|collision| collision.handle == blocker.collider()));
+    }
+
+    #[test]
+    pub fn collision_layers_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        let bullet = |x| {
+            EntityDescriptor::new(vec![x, 0.0, 0.0], EntityShape::Ball { radius: 0.05 })
+                .layers(CollisionLayers::projectile(None))
+        };
+        let first = collider.load_entity_with(bullet(0.0)).unwrap();
+        collider.load_entity_with(bullet(1.0)).unwrap();
+        let player = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![0.0, 0.0, 5.0], EntityShape::default())
+                    .layers(CollisionLayers::player(None)),
+            )
+            .unwrap();
+        collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![1.0, 0.0, 5.0], EntityShape::Ball { radius: 0.3 })
+                    .layers(CollisionLayers::pickup()),
+            )
+            .unwrap();
+
+        let movement = collider
+            .calculate_movement(first, Vec::new(), vec![2.0, 0.0, 0.0])
+            .unwrap();
+        assert!((movement.next_position.x - 2.0).abs() < 1.0e-3);
+        let movement = collider
+            .calculate_movement(player, Vec::new(), vec![2.0, 0.0, 0.0])
+            .unwrap();
+        assert!((movement.next_position.x - 2.0).abs() < 1.0e-3);
+
+        let hitscan = HitFilter::default().layers(CollisionLayers::projectile(None));
+        let hit = collider
+            .raycast(vec![-1.0, 0.0, 5.0], vec![1.0, 0.0, 0.0], 10.0, &hitscan)
+            .unwrap();
+        assert_eq!(hit.target, EventTarget::Entity(player));
+
+        collider
+            .set_entity_layers(player, CollisionLayers::player(Some(0)))
+            .unwrap();
+        let friendly = HitFilter::default().layers(CollisionLayers::projectile(Some(0)));
+        assert!(collider
+            .raycast(vec![-1.0, 0.0, 5.0], vec![1.0, 0.0, 0.0], 10.0, &friendly)
+            .is_none());
+    }
+
+    #[test]
+    pub fn entity_tag_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        let player = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![2.0, 0.0, 0.0], EntityShape::default())
+                    .tag(EntityKind::Player, 7),
+            )
+            .unwrap();
+        let bullet = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.05 })
+                    .tag(EntityKind::Projectile, 3),
+            )
+            .unwrap();
+        collider.run_step();
+
+        let movement = collider
+            .calculate_movement(bullet, Vec::new(), vec![4.0, 0.0, 0.0])
+            .unwrap();
+        let hit = movement.collisions.first().unwrap();
+        assert_eq!(
+            collider.collider_tag(hit.handle),
+            Some(EntityTag::new(EntityKind::Player, 7))
+        );
+
+        let zone = collider
+            .add_trigger(
+                "zone".to_string(),
+                vec![0.0, 10.0, 0.0],
+                EntityShape::Ball { radius: 1.0 },
+            )
+            .unwrap();
+        let tag = collider.collider_tag(zone.collider()).unwrap();
+        assert_eq!(TriggerId::from_tag(tag), Some(zone));
+
+        // Entities loaded again by a restore keep their tag
+        let snapshot = collider.snapshot();
+        collider.unload_entity(player).unwrap();
+        let restored = collider.restore(&snapshot).unwrap()[&player];
+        assert_eq!(
+            collider.collider_tag(restored.collider()),
+            Some(EntityTag::new(EntityKind::Player, 7))
+        );
+
+        collider.set_entity_tag(restored, None).unwrap();
+        assert_eq!(collider.collider_tag(restored.collider()), None);
+    }
+
+    #[test]
+    pub fn dynamic_body_test() {
+        let mut collider = GameCollider::default();
+        collider.add_tri_mesh(
+            vec![
+                Point3::new(-10.0, 0.0, -10.0),
+                Point3::new(10.0, 0.0, -10.0),
+                Point3::new(10.0, 0.0, 10.0),
+                Point3::new(-10.0, 0.0, 10.0),
+            ],
+            vec![[0, 2, 1], [0, 3, 2]],
+        );
+        let grenade = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![0.0, 2.0, 0.0], EntityShape::Ball { radius: 0.1 })
+                    .dynamic(DynamicBody {
+                        restitution: 0.8,
+                        ccd: true,
+                        ..DynamicBody::default()
+                    }),
+            )
+            .unwrap();
+        let player = collider
+            .load_entity(vec![5.0, 1.0, 0.0], EntityShape::default())
+            .unwrap();
+
+        assert_eq!(
+            collider
+                .calculate_movement(grenade, Vec::new(), vec![1.0, 0.0, 0.0])
+                .err(),
+            Some(CollisionError::NotKinematic(grenade.body()))
+        );
+        assert_eq!(
+            collider.apply_impulse(player, vec![1.0, 0.0, 0.0]),
+            Err(CollisionError::NotDynamic(player.body()))
+        );
+
+        let mut bounced = false;
+        for _ in 0..90 {
+            collider.run_step();
+            let transform = collider
+                .dynamic_transforms()
+                .into_iter()
+                .find(|transform| transform.entity_id == grenade)
+                .unwrap();
+            assert!(transform.translation[1] > 0.0);
+            bounced |= transform.linear_velocity[1] > 0.5;
+        }
+        assert!(bounced);
+
+        collider
+            .apply_impulse(grenade, vec![1.0, 0.0, 0.0])
+            .unwrap();
+        for _ in 0..10 {
+            collider.run_step();
+        }
+        assert!(collider.get_entity(grenade).unwrap().translation().x > 0.1);
+    }
+
+    #[test]
+    pub fn projectile_sweep_test() {
+        let mut collider = GameCollider::default();
+        // Infinitely thin wall at x = 5
+        let wall = collider.add_tri_mesh(
+            vec![
+                Point3::new(5.0, -5.0, -5.0),
+                Point3::new(5.0, 5.0, -5.0),
+                Point3::new(5.0, 5.0, 5.0),
+                Point3::new(5.0, -5.0, 5.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+        );
+        let bullet = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.05 })
+            .unwrap();
+        let player = collider
+            .load_entity(vec![3.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        collider.run_step();
+
+        // A single tick at 1000 units per second
+        let request = |exclude_colliders| MovementRequest {
+            entity_id: bullet,
+            exclude_colliders,
+            desired: vec![1000.0 / 60.0, 0.0, 0.0],
+        };
+        let sweeps = collider.sweep_projectiles(vec![request(Vec::new())]);
+        let sweep = sweeps[0].as_ref().unwrap();
+        let hit = sweep.hit.as_ref().unwrap();
+        assert_eq!(hit.target, EventTarget::Entity(player));
+        assert!((hit.distance - 2.45).abs() < 1.0e-3);
+        assert!((sweep.next_position.x - 2.45).abs() < 1.0e-3);
+        assert!((sweep.time_of_impact - 2.45 / (1000.0 / 60.0)).abs() < 1.0e-4);
+
+        let sweeps = collider.sweep_projectiles(vec![request(vec![player.collider()])]);
+        let hit = sweeps[0].as_ref().unwrap().hit.clone().unwrap();
+        assert_eq!(hit.target.collider(), wall);
+        assert!((hit.point.x - 5.0).abs() < 1.0e-3);
+
+        let sweeps = collider.sweep_projectiles(vec![MovementRequest {
+            entity_id: bullet,
+            exclude_colliders: Vec::new(),
+            desired: vec![0.0, 1.0, 0.0],
+        }]);
+        let sweep = sweeps[0].as_ref().unwrap();
+        assert_eq!(sweep.hit, None);
+        assert_eq!(sweep.time_of_impact, 1.0);
+    }
+
+    #[test]
+    pub fn fixed_step_test() {
+        let mut collider = GameCollider::default();
+        collider.set_tick_rate(30, 3).unwrap();
+        assert!((collider.fixed_dt() - 1.0 / 30.0).abs() < 1.0e-6);
+        assert!(collider.set_tick_rate(0, 3).is_err());
+
+        let grenade = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.1 })
+                    .dynamic(DynamicBody::default()),
+            )
+            .unwrap();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        collider
+            .set_linear_velocity(grenade, vec![3.0, 0.0, 0.0])
+            .unwrap();
+
+        // Two seconds of ticks with jitter end up in the same place as even ones
+        for elapsed in [20, 45, 30, 38, 17, 50].iter().cycle().take(60) {
+            for _ in 0..collider.advance(Duration::from_millis(*elapsed)) {
+                collider.run_step();
+            }
+        }
+        assert_eq!(collider.current_tick(), 60);
+        let x = collider.get_entity(grenade).unwrap().translation().x;
+        assert!((x - 6.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    pub fn shared_map_test() {
+        let map = MapGeometry::load("./data/environment.gltf".to_string());
+        let first = GameCollider::with_map(&map);
+        let mut second = GameCollider::with_map(&map);
+        assert_eq!(first.map_surfaces.len(), map.collider_count());
+        assert_eq!(second.map_surfaces.len(), map.collider_count());
+
+        // Both worlds point at the same trimesh instead of a copy
+        let shape = |collider: &GameCollider| {
+            let handle = *collider
+                .map_surfaces
+                .keys()
+                .min_by_key(|handle| handle.into_raw_parts())
+                .unwrap();
+            collider.colliders[handle].shared_shape().0.clone()
+        };
+        assert!(Arc::ptr_eq(&shape(&first), &shape(&second)));
+
+        // Entities only exist in their own world
+        let entity = second
+            .load_entity(vec![0.0, 50.0, 0.0], EntityShape::default())
+            .unwrap();
+        assert!(second.get_entity(entity).is_ok());
+        assert!(first.get_entity(entity).is_err());
+    }
+
+    #[test]
+    pub fn overlap_sphere_test() {
+        let mut collider = GameCollider::default();
+        collider.add_tri_mesh(
+            vec![
+                Point3::new(5.0, -5.0, -5.0),
+                Point3::new(5.0, 5.0, -5.0),
+                Point3::new(5.0, 5.0, 5.0),
+                Point3::new(5.0, -5.0, 5.0),
+            ],
+            vec![[0, 1, 2], [0, 2, 3]],
+        );
+        let player = |collider: &mut GameCollider, x| {
+            collider
+                .load_entity_with(
+                    EntityDescriptor::new(vec![x, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+                        .layers(CollisionLayers::player(None)),
+                )
+                .unwrap()
+        };
+        let near = player(&mut collider, 2.0);
+        let behind_wall = player(&mut collider, 8.0);
+        player(&mut collider, 20.0);
+        collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![-2.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+                    .layers(CollisionLayers::pickup()),
+            )
+            .unwrap();
+        collider.run_step();
+
+        let hits =
+            collider.overlap_sphere(vec![0.0, 0.0, 0.0], 10.0, CollisionLayers::projectile(None));
+        let entities: Vec<_> = hits.iter().map(|hit| hit.entity_id).collect();
+        assert_eq!(entities, vec![near, behind_wall]);
+        assert!((hits[0].distance - 1.5).abs() < 1.0e-4);
+        assert!((hits[0].direction.x - 1.0).abs() < 1.0e-4);
+        assert!((hits[1].distance - 7.5).abs() < 1.0e-4);
+
+        let visible: Vec<_> = hits
+            .iter()
+            .filter(|hit| {
+                collider.line_of_sight(vec![0.0, 0.0, 0.0], hit.point.coords.as_slice().to_vec())
+            })
+            .map(|hit| hit.entity_id)
+            .collect();
+        assert_eq!(visible, vec![near]);
+    }
+
+    #[test]
+    pub fn walk_into_hitboxes_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        // A thin pole whose hitboxes stick out of it, so only the hitboxes could stop a walker early
+        collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![2.0, 0.0, 0.0], EntityShape::Ball { radius: 0.1 })
+                    .layers(CollisionLayers::player(None))
+                    .hitboxes(Hitbox::humanoid()),
+            )
+            .unwrap();
+        let walker = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+        collider.run_step();
+
+        let movement = collider
+            .calculate_movement(walker, Vec::new(), vec![4.0, 0.0, 0.0])
+            .unwrap();
+        // Stops at the pole (1.7), the torso hitbox would have stopped it short of 1.6
+        assert!(movement.next_position.x > 1.6);
+    }
+
+    #[test]
+    pub fn hitbox_test() {
+        let mut collider = GameCollider::default();
+        let player = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![0.0, 0.0, 0.0], EntityShape::default())
+                    .layers(CollisionLayers::player(None))
+                    .tag(EntityKind::Player, 7)
+                    .hitboxes(Hitbox::humanoid()),
+            )
+            .unwrap();
+        collider.run_step();
+        assert_eq!(collider.get_entity_hitboxes(player).unwrap().len(), 3);
+
+        let shoot = |collider: &GameCollider, y: f32, z: f32| {
+            let filter = HitFilter::default().layers(CollisionLayers::projectile(None));
+            collider
+                .raycast(vec![-5.0, y, z], vec![1.0, 0.0, 0.0], 10.0, &filter)
+                .map(|hit| hit.target)
+        };
+        let part = |part| {
+            Some(EventTarget::Hitbox {
+                entity: player,
+                part,
+            })
+        };
+        assert_eq!(shoot(&collider, 0.5, 0.0), part(HitboxPart::Head));
+        assert_eq!(shoot(&collider, 0.1, 0.0), part(HitboxPart::Torso));
+        assert_eq!(shoot(&collider, -0.4, 0.0), part(HitboxPart::Legs));
+        // Grazes the capsule but misses the head, projectiles only hit the hitboxes
+        assert_eq!(shoot(&collider, 0.5, 0.17), None);
+        let hit = collider
+            .raycast(
+                vec![-5.0, 0.5, 0.0],
+                vec![1.0, 0.0, 0.0],
+                10.0,
+                &HitFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            collider.collider_tag(hit.target.collider()),
+            Some(EntityTag::new(EntityKind::Player, 7))
+        );
+
+        // Lying on the side, 90 degrees around +Z puts the head towards -X
+        let half = std::f32::consts::FRAC_PI_4.sin();
+        collider
+            .update_entity_rotation(player, 0.0, 0.0, half, half)
+            .unwrap();
+        collider.run_step();
+        assert_eq!(shoot(&collider, 0.0, 0.0), part(HitboxPart::Head));
+        assert_eq!(shoot(&collider, 0.5, 0.0), None);
+
+        let bullet = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![-0.5, 0.0, 0.0], EntityShape::Ball { radius: 0.05 })
+                    .layers(CollisionLayers::projectile(None)),
+            )
+            .unwrap();
+        let events = collider.run_step();
+        assert!(events.iter().any(|event| {
+            matches!(event, PhysicsEvent::IntersectionStarted { first, second }
+                if [*first, *second].contains(&part(HitboxPart::Head).unwrap()))
+                && event.involves(bullet)
+        }));
+        assert!(events.iter().all(|event| {
+            !matches!(event, PhysicsEvent::ContactStarted { .. }) || !event.involves(bullet)
+        }));
+
+        // Excluding the shooter's collider excludes its hitboxes too
+        let mut sweep = |exclude_colliders| {
+            collider.sweep_projectiles(vec![MovementRequest {
+                entity_id: bullet,
+                exclude_colliders,
+                desired: vec![2.0, 0.0, 0.0],
+            }])[0]
+                .as_ref()
+                .unwrap()
+                .hit
+                .as_ref()
+                .map(|hit| hit.target)
+        };
+        assert_eq!(sweep(Vec::new()), part(HitboxPart::Head));
+        assert_eq!(sweep(vec![player.collider()]), None);
+
+        let snapshot = collider.snapshot();
+        collider.unload_entity(player).unwrap();
+        assert_eq!(shoot(&collider, 0.0, 0.0), None);
+        let restored = collider.restore(&snapshot).unwrap()[&player];
+        assert_eq!(
+            collider.get_entity_hitboxes(restored).unwrap(),
+            Hitbox::humanoid().as_slice()
+        );
+        assert_eq!(
+            shoot(&collider, 0.0, 0.0),
+            Some(EventTarget::Hitbox {
+                entity: restored,
+                part: HitboxPart::Head,
+            })
+        );
+    }
+
+    #[test]
+    pub fn stance_test() {
+        let mut collider = GameCollider::default();
+        let quad = |y: f32, size: f32| {
+            (
+                vec![
+                    Point3::new(-size, y, -size),
+                    Point3::new(size, y, -size),
+                    Point3::new(size, y, size),
+                    Point3::new(-size, y, size),
+                ],
+                vec![[0, 1, 2], [0, 2, 3]],
+            )
+        };
+        let (floor, indices) = quad(0.0, 10.0);
+        collider.add_tri_mesh(floor, indices);
+        // Standing on the floor, the default capsule is 1.4 units tall
+        let player = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![0.0, 0.7, 0.0], EntityShape::default())
+                    .layers(CollisionLayers::player(None))
+                    .hitboxes(Hitbox::humanoid()),
+            )
+            .unwrap();
+        collider.run_step();
+        assert_eq!(collider.get_entity_stance(player), Ok(Stance::Standing));
+
+        assert_eq!(
+            collider.set_entity_stance(player, Stance::Crouching),
+            Ok(true)
+        );
+        assert_eq!(collider.get_entity_stance(player), Ok(Stance::Crouching));
+        let body = collider.get_entity(player).unwrap();
+        assert!((body.translation().y - 0.42).abs() < 1.0e-4);
+        let height = collider
+            .get_entity_collider(player)
+            .unwrap()
+            .compute_aabb()
+            .extents()
+            .y;
+        assert!((height - 0.84).abs() < 1.0e-4);
+        // The loaded shape and hitboxes stay what the entity stands up to
+        assert_eq!(
+            collider.get_entity_shape(player),
+            Ok(&EntityShape::default())
+        );
+
+        let shoot = |collider: &GameCollider, y: f32| {
+            let filter = HitFilter::default().layers(CollisionLayers::projectile(None));
+            collider
+                .raycast(vec![-5.0, y, 3.0], vec![1.0, 0.0, 0.0], 10.0, &filter)
+                .map(|hit| hit.target)
+        };
+        let head = Some(EventTarget::Hitbox {
+            entity: player,
+            part: HitboxPart::Head,
+        });
+
+        // Crawl under a ceiling at 1.0, too low to stand up
+        let (ceiling, indices) = quad(1.0, 1.0);
+        collider.add_tri_mesh(ceiling, indices);
+        // No step in between, the headroom check still sees the new ceiling
+        assert_eq!(
+            collider.set_entity_stance(player, Stance::Standing),
+            Ok(false)
+        );
+        assert_eq!(collider.get_entity_stance(player), Ok(Stance::Crouching));
+
+        let snapshot = collider.snapshot();
+        let entity = collider.get_mut_entity(player).unwrap();
+        entity.set_next_kinematic_translation(Vector::new(0.0, 0.42, 3.0));
+        collider.run_step();
+        assert_eq!(shoot(&collider, 0.72), head);
+        assert_eq!(
+            collider.set_entity_stance(player, Stance::Standing),
+            Ok(true)
+        );
+        // Hits follow the new height without a step
+        assert!((collider.get_entity(player).unwrap().translation().y - 0.7).abs() < 1.0e-4);
+        assert_eq!(shoot(&collider, 1.2), head);
+        assert_eq!(
+            shoot(&collider, 0.72),
+            Some(EventTarget::Hitbox {
+                entity: player,
+                part: HitboxPart::Torso,
+            })
+        );
+
+        collider.restore(&snapshot).unwrap();
+        assert_eq!(collider.get_entity_stance(player), Ok(Stance::Crouching));
+        let height = collider
+            .get_entity_collider(player)
+            .unwrap()
+            .compute_aabb()
+            .extents()
+            .y;
+        assert!((height - 0.84).abs() < 1.0e-4);
+    }
+
+    #[test]
+    pub fn rollback_history_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        collider.set_history_length(10);
+        let target = collider
+            .load_entity(vec![5.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        collider.run_step();
+        let snapshot = collider.snapshot();
+        assert_eq!(snapshot.tick, collider.current_tick());
+
+        // Mispredicted: the target dodged out of the line of fire
+        let entity = collider.get_mut_entity(target).unwrap();
+        entity.set_next_kinematic_translation(Vector::new(5.0, 0.0, 2.0));
+        collider.run_step();
+        let resimulated_tick = collider.current_tick();
+
+        collider.restore(&snapshot).unwrap();
+        assert_eq!(collider.current_tick(), snapshot.tick);
+        let filter = HitFilter::default();
+        let shot = |collider: &GameCollider| {
+            collider.raycast_at_tick(
+                resimulated_tick,
+                vec![0.0, 0.0, 0.0],
+                vec![1.0, 0.0, 0.0],
+                10.0,
+                &filter,
+            )
+        };
+        assert_eq!(
+            shot(&collider),
+            Err(CollisionError::UnknownTick(resimulated_tick))
+        );
+
+        // Re-simulated: it stayed where it was
+        collider.run_step();
+        assert_eq!(collider.current_tick(), resimulated_tick);
+        let hit = shot(&collider).unwrap().unwrap();
+        assert_eq!(hit.target, EventTarget::Entity(target));
+        assert!((hit.distance - 4.5).abs() < 1.0e-4);
+    }
+
+    #[test]
+    pub fn rewind_stance_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        collider.set_history_length(10);
+        // Feet at 0.0, the head is hit at 1.2 standing but not crouching
+        let player = collider
+            .load_entity_with(
+                EntityDescriptor::new(vec![5.0, 0.7, 0.0], EntityShape::default())
+                    .layers(CollisionLayers::player(None))
+                    .hitboxes(Hitbox::humanoid()),
+            )
+            .unwrap();
+        collider.run_step();
+        let standing_tick = collider.current_tick();
+        assert_eq!(
+            collider.set_entity_stance(player, Stance::Crouching),
+            Ok(true)
+        );
+        collider.run_step();
+
+        let filter = HitFilter::default().layers(CollisionLayers::projectile(None));
+        let shot = |collider: &GameCollider, tick| {
+            collider
+                .raycast_at_tick(
+                    tick,
+                    vec![0.0, 1.2, 0.0],
+                    vec![1.0, 0.0, 0.0],
+                    10.0,
+                    &filter,
+                )
+                .unwrap()
+                .map(|hit| hit.target)
+        };
+        assert_eq!(
+            shot(&collider, standing_tick),
+            Some(EventTarget::Hitbox {
+                entity: player,
+                part: HitboxPart::Head,
+            })
+        );
+        assert_eq!(shot(&collider, collider.current_tick()), None);
+
+        // And the other way around, back up the crouched tick misses the head again
+        let crouching_tick = collider.current_tick();
+        assert_eq!(
+            collider.set_entity_stance(player, Stance::Standing),
+            Ok(true)
+        );
+        collider.run_step();
+        assert!(shot(&collider, collider.current_tick()).is_some());
+        assert_eq!(shot(&collider, crouching_tick), None);
+    }
+
+    #[test]
+    pub fn trigger_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        let zone = collider
+            .add_trigger(
+                "capture_zone".to_string(),
+                vec![3.0, 0.0, 0.0],
+                EntityShape::Cuboid {
+                    half_extents: [1.0, 1.0, 1.0],
+                },
+            )
+            .unwrap();
+        let player = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        assert_eq!(collider.find_trigger("capture_zone"), Some(zone));
+
+        let step_to = |collider: &mut GameCollider, x: f32| {
+            let entity = collider.get_mut_entity(player).unwrap();
+            entity.set_next_kinematic_translation(Vector::new(x, 0.0, 0.0));
+            collider.run_step()
+        };
+        let is_trigger_event = |event: &PhysicsEvent| {
+            matches!(
+                event,
+                PhysicsEvent::TriggerEntered { .. }
+                    | PhysicsEvent::TriggerStayed { .. }
+                    | PhysicsEvent::TriggerExited { .. }
+            )
+        };
+
+        assert!(!step_to(&mut collider, 0.0).iter().any(is_trigger_event));
+        let events = step_to(&mut collider, 3.0);
+        assert!(events.contains(&PhysicsEvent::TriggerEntered {
+            trigger: zone,
+            entity: player
+        }));
+        assert!(
+            events.contains(&PhysicsEvent::IntersectionStarted {
+                first: EventTarget::Trigger(zone),
+                second: EventTarget::Entity(player)
+            }) || events.contains(&PhysicsEvent::IntersectionStarted {
+                first: EventTarget::Entity(player),
+                second: EventTarget::Trigger(zone)
+            })
+        );
+        assert!(
+            step_to(&mut collider, 3.1).contains(&PhysicsEvent::TriggerStayed {
+                trigger: zone,
+                entity: player
+            })
+        );
+        assert!(collider.trigger_occupants(zone).unwrap().contains(&player));
+        assert!(
+            step_to(&mut collider, 8.0).contains(&PhysicsEvent::TriggerExited {
+                trigger: zone,
+                entity: player
+            })
+        );
+
+        step_to(&mut collider, 3.0);
+        collider.unload_entity(player).unwrap();
+        assert!(collider.run_step().contains(&PhysicsEvent::TriggerExited {
+            trigger: zone,
+            entity: player
+        }));
+        assert!(collider.trigger_occupants(zone).unwrap().is_empty());
+
+        collider.remove_trigger(zone).unwrap();
+        assert_eq!(
+            collider.remove_trigger(zone),
+            Err(CollisionError::UnknownTrigger(zone))
+        );
+    }
+
+    #[test]
+    pub fn map_triggers_test() {
+        // A floor cube squashed to 0.1 units at y = -1 and a unit cube trigger_zone at y = 2
+        let map = MapGeometry::load("./data/triggers.gltf".to_string());
+        assert_eq!(map.collider_count(), 1);
+        assert_eq!(map.trigger_count(), 1);
+        let mut collider = GameCollider::with_map(&map);
+        let zone = collider.find_trigger("trigger_zone").unwrap();
+        collider.run_step();
+
+        // The ray passes through the trigger and stops on the transformed floor
+        let hit = collider
+            .raycast(
+                vec![0.0, 5.0, 0.0],
+                vec![0.0, -1.0, 0.0],
+                10.0,
+                &HitFilter::default(),
+            )
+            .unwrap();
+        assert!(matches!(hit.target, EventTarget::Map(_)));
+        assert!((hit.point.y + 0.95).abs() < 1.0e-4);
+
+        let player = collider
+            .load_entity(vec![0.0, 2.0, 0.0], EntityShape::Ball { radius: 0.2 })
+            .unwrap();
+        let events = collider.run_step();
+        assert!(events.contains(&PhysicsEvent::TriggerEntered {
+            trigger: zone,
+            entity: player,
+        }));
+
+        // Other prefixes can still be loaded on top
+        let triggers = collider
+            .load_map_triggers("./data/triggers.gltf".to_string(), "trigger_z")
+            .unwrap();
+        assert_eq!(triggers.len(), 1);
+        assert_ne!(triggers[0], zone);
+        assert_eq!(collider.trigger_name(triggers[0]), Some("trigger_zone"));
+    }
+
+    #[test]
+    pub fn snapshot_restore_test() {
+        let mut collider = GameCollider::default();
+        let zone = collider
+            .add_trigger(
+                "spawn".to_string(),
+                vec![0.0, 0.0, 0.0],
+                EntityShape::Ball { radius: 2.0 },
+            )
+            .unwrap();
+        let player = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+        collider.run_step();
+        let snapshot = collider.snapshot();
+
+        let serialized = toml::to_string(&snapshot).unwrap();
+        assert_eq!(
+            toml::from_str::<WorldSnapshot>(&serialized).unwrap(),
+            snapshot
+        );
+
+        let movement = collider
+            .calculate_movement(player, Vec::new(), vec![5.0, 0.0, 0.0])
+            .unwrap();
+        let entity = collider.get_mut_entity(player).unwrap();
+        entity.set_next_kinematic_translation(entity.translation() + movement.next_position);
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        let late = collider
+            .load_entity(vec![9.0, 0.0, 0.0], EntityShape::default())
+            .unwrap();
+        collider.run_step();
+        assert!(collider.trigger_occupants(zone).unwrap().is_empty());
+
+        let entity_ids = collider.restore(&snapshot).unwrap();
+        assert_eq!(entity_ids[&player], player);
+        assert!(collider.get_entity(late).is_err());
+        assert_eq!(collider.get_entity(player).unwrap().translation().x, 0.0);
+        assert!(collider.trigger_occupants(zone).unwrap().contains(&player));
+        assert_eq!(collider.snapshot().gravity, snapshot.gravity);
+
+        collider.unload_entity(player).unwrap();
+        let entity_ids = collider.restore(&snapshot).unwrap();
+        let restored = entity_ids[&player];
+        assert_ne!(restored, player);
+        assert_eq!(
+            collider.get_entity_shape(restored),
+            Ok(&EntityShape::default())
+        );
+        assert!(collider
+            .trigger_occupants(zone)
+            .unwrap()
+            .contains(&restored));
+    }
+
+    #[test]
+    pub fn restore_body_type_test() {
+        let mut collider = GameCollider::default();
+        let crate_id = collider
+            .load_entity(vec![0.0, 5.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        let kinematic = collider.snapshot();
+        let mut dynamic = kinematic.clone();
+        for entity in dynamic.entities.iter_mut() {
+            entity.dynamic = Some(DynamicBody::default());
+        }
+
+        let entity_ids = collider.restore(&dynamic).unwrap();
+        assert_eq!(entity_ids[&crate_id], crate_id);
+        assert!(collider.get_entity(crate_id).unwrap().is_dynamic());
+        collider.run_step();
+        assert!(collider.get_entity(crate_id).unwrap().translation().y < 5.0);
+        assert_eq!(collider.dynamic_transforms().len(), 1);
+
+        collider.restore(&kinematic).unwrap();
+        assert!(collider.get_entity(crate_id).unwrap().is_kinematic());
+        assert!(collider.dynamic_transforms().is_empty());
+        assert_eq!(collider.snapshot().entities, kinematic.entities);
+    }
+
+    #[test]
+    pub fn rewind_test() {
+        let mut collider = GameCollider::default();
+        collider.set_gravity(vec![0.0, 0.0, 0.0]);
+        collider.set_history_length(10);
+        let shooter = collider
+            .load_entity(vec![0.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        let target = collider
+            .load_entity(vec![5.0, 0.0, 0.0], EntityShape::Ball { radius: 0.5 })
+            .unwrap();
+        collider.run_step();
+        let seen_tick = collider.current_tick();
+
+        for z in 1..=3 {
+            let entity = collider.get_mut_entity(target).unwrap();
+            entity.set_next_kinematic_translation(Vector::new(5.0, 0.0, z as f32 * 2.0));
+            collider.run_step();
+        }
+
+        let filter = HitFilter::default().exclude_entity(shooter);
+        let shot = |collider: &GameCollider, tick| {
+            collider
+                .raycast_at_tick(
+                    tick,
+                    vec![0.0, 0.0, 0.0],
+                    vec![1.0, 0.0, 0.0],
+                    10.0,
+                    &filter,
+                )
+                .unwrap()
+        };
+        assert!(collider
+            .raycast(vec![0.0, 0.0, 0.0], vec![1.0, 0.0, 0.0], 10.0, &filter)
+            .is_none());
+        assert!(shot(&collider, collider.current_tick()).is_none());
+        let hit = shot(&collider, seen_tick).unwrap();
+        assert_eq!(hit.target, EventTarget::Entity(target));
+        assert!((hit.distance - 4.5).abs() < 1.0e-4);
+
+        let swept = collider
+            .shape_cast_at_tick(
+                seen_tick,
+                &EntityShape::Ball { radius: 0.1 },
+                vec![0.0, 0.0, 0.0],
+                vec![1.0, 0.0, 0.0],
+                10.0,
+                &filter,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(swept.target, EventTarget::Entity(target));
+        assert!((swept.point.x - 4.5).abs() < 1.0e-3);
+
+        for _ in 0..10 {
+            collider.run_step();
+        }
+        assert_eq!(
+            collider.raycast_at_tick(seen_tick, vec![0.0; 3], vec![1.0, 0.0, 0.0], 10.0, &filter),
+            Err(CollisionError::UnknownTick(seen_tick))
+        );
+        assert_eq!(
+            collider.oldest_history_tick(),
+            Some(collider.current_tick() - 9)
+        );
     }
 }
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use rapier3d::{
+    control::{CharacterAutostep, CharacterLength, KinematicCharacterController},
+    na::Unit,
+    prelude::Vector,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::CollisionError;
+
+/// A length which is either absolute or relative to the size of the entity's shape.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControllerLength {
+    Relative(f32),
+    Absolute(f32),
+}
+
+impl From<ControllerLength> for CharacterLength {
+    fn from(length: ControllerLength) -> Self {
+        match length {
+            ControllerLength::Relative(value) => CharacterLength::Relative(value),
+            ControllerLength::Absolute(value) => CharacterLength::Absolute(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutostepConfig {
+    pub max_height: ControllerLength,
+    pub min_width: ControllerLength,
+    pub include_dynamic_bodies: bool,
+}
+
+impl Default for AutostepConfig {
+    fn default() -> Self {
+        Self {
+            max_height: ControllerLength::Relative(0.25),
+            min_width: ControllerLength::Relative(0.5),
+            include_dynamic_bodies: true,
+        }
+    }
+}
+
+/// Movement feel of the kinematic character controller.
+///
+/// Angles are in degrees. The default is the profile Scrape has been using so far.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CharacterControllerConfig {
+    pub up: [f32; 3],
+    /// Gap kept between the entity and obstacles
+    pub offset: ControllerLength,
+    pub slide: bool,
+    /// Slopes steeper than this can't be climbed
+    pub max_slope_climb_angle: f32,
+    /// Slopes steeper than this are slid down automatically
+    pub min_slope_slide_angle: f32,
+    pub autostep: Option<AutostepConfig>,
+    pub snap_to_ground: Option<ControllerLength>,
+}
+
+impl Default for CharacterControllerConfig {
+    fn default() -> Self {
+        Self {
+            up: [0.0, 1.0, 0.0],
+            offset: ControllerLength::Relative(0.01),
+            slide: true,
+            max_slope_climb_angle: 60.0,
+            min_slope_slide_angle: 30.0,
+            autostep: Some(AutostepConfig::default()),
+            snap_to_ground: Some(ControllerLength::Relative(0.2)),
+        }
+    }
+}
+
+impl CharacterControllerConfig {
+    pub fn to_controller(&self) -> Result<KinematicCharacterController, CollisionError> {
+        let up = Unit::try_new(Vector::new(self.up[0], self.up[1], self.up[2]), 1.0e-6).ok_or(
+            CollisionError::InvalidConfig("the up vector can't be zero".to_string()),
+        )?;
+
+        Ok(KinematicCharacterController {
+            up,
+            offset: self.offset.into(),
+            slide: self.slide,
+            autostep: self.autostep.as_ref().map(|autostep| CharacterAutostep {
+                max_height: autostep.max_height.into(),
+                min_width: autostep.min_width.into(),
+                include_dynamic_bodies: autostep.include_dynamic_bodies,
+            }),
+            max_slope_climb_angle: self.max_slope_climb_angle.to_radians(),
+            min_slope_slide_angle: self.min_slope_slide_angle.to_radians(),
+            snap_to_ground: self.snap_to_ground.map(CharacterLength::from),
+        })
+    }
+}
+
+/// The default controller plus named profiles (e.g. per entity kind), as read from a config file:
+///
+/// ```toml
+/// [default]
+/// max_slope_climb_angle = 50.0
+///
+/// [profiles.bullet]
+/// slide = false
+/// autostep = {}
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControllerProfiles {
+    pub default: CharacterControllerConfig,
+    pub profiles: HashMap<String, CharacterControllerConfig>,
+}
+
+impl ControllerProfiles {
+    pub fn from_toml(contents: &str) -> Result<Self, CollisionError> {
+        toml::from_str(contents).map_err(|err| CollisionError::InvalidConfig(err.to_string()))
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, CollisionError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| CollisionError::ConfigUnreadable(path.to_string(), err.to_string()))?;
+        Self::from_toml(&contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CharacterControllerConfig, ControllerLength, ControllerProfiles};
+
+    #[test]
+    fn parse_profiles_test() {
+        let profiles = ControllerProfiles::from_toml(
+            r#"
+            [default]
+            max_slope_climb_angle = 50.0
+            offset = { absolute = 0.02 }
+
+            [profiles.bullet]
+            slide = false
+            snap_to_ground = { relative = 0.1 }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(profiles.default.max_slope_climb_angle, 50.0);
+        assert_eq!(profiles.default.offset, ControllerLength::Absolute(0.02));
+        assert_eq!(profiles.default.min_slope_slide_angle, 30.0);
+
+        let bullet = &profiles.profiles["bullet"];
+        assert!(!bullet.slide);
+        assert_eq!(bullet.snap_to_ground, Some(ControllerLength::Relative(0.1)));
+
+        let controller = CharacterControllerConfig::default()
+            .to_controller()
+            .unwrap();
+        assert_eq!(controller.max_slope_climb_angle, 60.0_f32.to_radians());
+
+        let invalid = CharacterControllerConfig {
+            up: [0.0, 0.0, 0.0],
+            ..Default::default()
+        };
+        assert!(invalid.to_controller().is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use rapier3d::prelude::{ColliderHandle, RigidBodyHandle};
+use serde::{Deserialize, Serialize};
+
+use crate::hitbox::Hitbox;
+use crate::layers::CollisionLayers;
+use crate::shape::EntityShape;
+use crate::stance::Stance;
+
+/// Pairs the rigid-body and collider handles of an entity created by
+/// [`GameCollider::load_entity`](crate::collider::GameCollider::load_entity).
+///
+/// Only the [`GameCollider`](crate::collider::GameCollider) can create one, so the two
+/// handles always belong together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "RawEntityId", into = "RawEntityId")]
+pub struct EntityId {
+    body: RigidBodyHandle,
+    collider: ColliderHandle,
+}
+
+/// Index and generation of both handles, rapier's handles are only serializable with its serde feature.
+#[derive(Serialize, Deserialize)]
+struct RawEntityId {
+    body: (u32, u32),
+    collider: (u32, u32),
+}
+
+impl From<RawEntityId> for EntityId {
+    fn from(raw: RawEntityId) -> Self {
+        Self {
+            body: RigidBodyHandle::from_raw_parts(raw.body.0, raw.body.1),
+            collider: ColliderHandle::from_raw_parts(raw.collider.0, raw.collider.1),
+        }
+    }
+}
+
+impl From<EntityId> for RawEntityId {
+    fn from(entity_id: EntityId) -> Self {
+        Self {
+            body: entity_id.body.into_raw_parts(),
+            collider: entity_id.collider.into_raw_parts(),
+        }
+    }
+}
+
+impl EntityId {
+    pub(crate) fn new(body: RigidBodyHandle, collider: ColliderHandle) -> Self {
+        Self { body, collider }
+    }
+
+    pub fn body(&self) -> RigidBodyHandle {
+        self.body
+    }
+
+    pub fn collider(&self) -> ColliderHandle {
+        self.collider
+    }
+}
+
+/// What kind of game object a collider belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum EntityKind {
+    Player = 1,
+    Projectile = 2,
+    Map = 3,
+    Trigger = 4,
+    Pickup = 5,
+    Other = 6,
+}
+
+impl EntityKind {
+    fn from_repr(kind: u8) -> Option<Self> {
+        match kind {
+            1 => Some(Self::Player),
+            2 => Some(Self::Projectile),
+            3 => Some(Self::Map),
+            4 => Some(Self::Trigger),
+            5 => Some(Self::Pickup),
+            6 => Some(Self::Other),
+            _ => None,
+        }
+    }
+}
+
+/// Game-level identity of a collider, packed into its `user_data` so a [`ColliderHandle`]
+/// resolves to the right player, bullet or map feature without a lookup table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EntityTag {
+    pub kind: EntityKind,
+    /// Id the game uses for the object, e.g. a player or bullet id
+    pub id: u64,
+}
+
+impl EntityTag {
+    pub fn new(kind: EntityKind, id: u64) -> Self {
+        Self { kind, id }
+    }
+
+    /// The kind lives in the upper 64 bits, so untagged colliders (`user_data` of `0`) have no tag.
+    pub(crate) fn to_user_data(self) -> u128 {
+        ((self.kind as u128) << 64) | self.id as u128
+    }
+
+    pub(crate) fn from_user_data(user_data: u128) -> Option<Self> {
+        let kind = EntityKind::from_repr((user_data >> 64) as u8)?;
+        Some(Self::new(kind, user_data as u64))
+    }
+}
+
+/// Physical properties of an entity which rapier simulates instead of the game moving it,
+/// e.g. grenades and props. Loaded with [`EntityDescriptor::dynamic`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DynamicBody {
+    pub mass: f32,
+    /// Bounciness, `0.0` stops dead and `1.0` keeps all of the speed
+    pub restitution: f32,
+    pub friction: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    /// Continuous collision detection, so small and fast bodies don't tunnel through walls
+    pub ccd: bool,
+}
+
+impl Default for DynamicBody {
+    fn default() -> Self {
+        Self {
+            mass: 1.0,
+            restitution: 0.0,
+            friction: 0.5,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            ccd: false,
+        }
+    }
+}
+
+/// Pose and velocity of a dynamic entity after a step, what the server broadcasts.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BodyTransform {
+    pub entity_id: EntityId,
+    pub translation: [f32; 3],
+    /// Quaternion as `[i, j, k, w]`
+    pub rotation: [f32; 4],
+    pub linear_velocity: [f32; 3],
+}
+
+/// Everything needed to load an entity with
+/// [`GameCollider::load_entity_with`](crate::collider::GameCollider::load_entity_with).
+#[derive(Debug, Clone)]
+pub struct EntityDescriptor {
+    pub spawn: Vec<f32>,
+    pub shape: EntityShape,
+    pub layers: CollisionLayers,
+    pub tag: Option<EntityTag>,
+    /// `None` for kinematic entities moved with the character controller
+    pub dynamic: Option<DynamicBody>,
+    pub hitboxes: Vec<Hitbox>,
+}
+
+impl EntityDescriptor {
+    pub fn new(spawn: Vec<f32>, shape: EntityShape) -> Self {
+        Self {
+            spawn,
+            shape,
+            layers: CollisionLayers::default(),
+            tag: None,
+            dynamic: None,
+            hitboxes: Vec::new(),
+        }
+    }
+
+    pub fn layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    pub fn tag(mut self, kind: EntityKind, id: u64) -> Self {
+        self.tag = Some(EntityTag::new(kind, id));
+        self
+    }
+
+    pub fn dynamic(mut self, body: DynamicBody) -> Self {
+        self.dynamic = Some(body);
+        self
+    }
+
+    pub fn hitboxes(mut self, hitboxes: Vec<Hitbox>) -> Self {
+        self.hitboxes = hitboxes;
+        self
+    }
+}
+
+/// Game-side state we keep next to the rapier bodies of every loaded entity.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct EntityState {
+    /// Shape the entity was loaded with, it keeps it while standing
+    pub shape: EntityShape,
+    pub layers: CollisionLayers,
+    pub tag: Option<EntityTag>,
+    pub dynamic: Option<DynamicBody>,
+    /// Hitboxes in the standing stance, like the shape
+    pub hitboxes: Vec<Hitbox>,
+    pub stance: Stance,
+    /// Name of the controller profile, `None` for the default controller
+    pub controller_profile: Option<String>,
+    /// Speed along the controller's up vector, from gravity and jumps
+    pub vertical_velocity: f32,
+    pub grounded: bool,
+    /// Multiplies the world gravity, `0.0` for entities which shouldn't fall (e.g. bullets)
+    pub gravity_scale: f32,
+}
+
+impl EntityState {
+    pub fn new(
+        shape: EntityShape,
+        layers: CollisionLayers,
+        tag: Option<EntityTag>,
+        dynamic: Option<DynamicBody>,
+    ) -> Self {
+        Self {
+            shape,
+            layers,
+            tag,
+            dynamic,
+            hitboxes: Vec::new(),
+            stance: Stance::Standing,
+            controller_profile: None,
+            vertical_velocity: 0.0,
+            grounded: false,
+            gravity_scale: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EntityKind, EntityTag};
+
+    #[test]
+    fn tag_user_data_test() {
+        let tag = EntityTag::new(EntityKind::Projectile, u64::MAX);
+        assert_eq!(EntityTag::from_user_data(tag.to_user_data()), Some(tag));
+        assert_eq!(EntityTag::from_user_data(0), None);
+        assert_eq!(EntityTag::from_user_data(u128::MAX), None);
+    }
+}
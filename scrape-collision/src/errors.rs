@@ -0,0 +1,32 @@
+use rapier3d::prelude::{ColliderHandle, RigidBodyHandle};
+use thiserror::Error;
+
+use crate::triggers::TriggerId;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CollisionError {
+    #[error("Entity {0:?} does not exist, it might have already been unloaded")]
+    UnknownEntity(RigidBodyHandle),
+    #[error("Collider {0:?} does not exist")]
+    UnknownCollider(ColliderHandle),
+    #[error("Collider {1:?} is not attached to entity {0:?}")]
+    MismatchedHandles(RigidBodyHandle, ColliderHandle),
+    #[error("Couldn't build a collider from the provided entity shape")]
+    InvalidShape,
+    #[error("Couldn't read config file {0}: {1}")]
+    ConfigUnreadable(String, String),
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+    #[error("Controller profile {0} does not exist")]
+    UnknownControllerProfile(String),
+    #[error("Trigger {0:?} does not exist")]
+    UnknownTrigger(TriggerId),
+    #[error("Tick {0} is not in the transform history")]
+    UnknownTick(u64),
+    #[error("Entity {0:?} is dynamic and can't be moved by the character controller")]
+    NotKinematic(RigidBodyHandle),
+    #[error("Entity {0:?} is kinematic, only dynamic entities take impulses")]
+    NotDynamic(RigidBodyHandle),
+    #[error("Rotation has to be a finite, non-zero quaternion")]
+    InvalidRotation,
+}
@@ -0,0 +1,183 @@
+use rapier3d::{
+    crossbeam::channel::{unbounded, Receiver},
+    geometry::{CollisionEvent, CollisionEventFlags, ContactForceEvent},
+    pipeline::ChannelEventCollector,
+    prelude::{ColliderHandle, Vector},
+};
+
+use crate::entity::EntityId;
+use crate::hitbox::HitboxPart;
+use crate::triggers::TriggerId;
+
+/// One side of a [`PhysicsEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventTarget {
+    Entity(EntityId),
+    /// A trimesh of a map attached with [`GameCollider::attach_map`](crate::collider::GameCollider::attach_map)
+    Map(ColliderHandle),
+    /// One of the hit volumes attached to an entity
+    Hitbox {
+        entity: EntityId,
+        part: HitboxPart,
+    },
+    /// A sensor added with [`GameCollider::add_trigger`](crate::collider::GameCollider::add_trigger)
+    Trigger(TriggerId),
+    /// A collider we don't know (anymore), e.g. one of an entity unloaded before the step
+    Collider(ColliderHandle),
+}
+
+impl EventTarget {
+    /// The hit collider, for a hitbox the one of its entity.
+    pub fn collider(&self) -> ColliderHandle {
+        match self {
+            Self::Entity(entity_id)
+            | Self::Hitbox {
+                entity: entity_id, ..
+            } => entity_id.collider(),
+            Self::Trigger(trigger) => trigger.collider(),
+            Self::Map(handle) | Self::Collider(handle) => *handle,
+        }
+    }
+
+    /// The entity itself or the one a hitbox belongs to.
+    pub fn entity(&self) -> Option<EntityId> {
+        match self {
+            Self::Entity(entity_id)
+            | Self::Hitbox {
+                entity: entity_id, ..
+            } => Some(*entity_id),
+            _ => None,
+        }
+    }
+}
+
+/// Something that happened between two colliders during
+/// [`GameCollider::run_step`](crate::collider::GameCollider::run_step).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhysicsEvent {
+    ContactStarted {
+        first: EventTarget,
+        second: EventTarget,
+    },
+    ContactStopped {
+        first: EventTarget,
+        second: EventTarget,
+        /// One of the colliders was removed, which ended the contact
+        removed: bool,
+    },
+    /// A sensor started overlapping another collider
+    IntersectionStarted {
+        first: EventTarget,
+        second: EventTarget,
+    },
+    IntersectionStopped {
+        first: EventTarget,
+        second: EventTarget,
+        removed: bool,
+    },
+    ContactForce {
+        first: EventTarget,
+        second: EventTarget,
+        total_force_magnitude: f32,
+        max_force_direction: Vector<f32>,
+    },
+    TriggerEntered {
+        trigger: TriggerId,
+        entity: EntityId,
+    },
+    /// The entity was already inside the trigger before this step
+    TriggerStayed {
+        trigger: TriggerId,
+        entity: EntityId,
+    },
+    TriggerExited {
+        trigger: TriggerId,
+        entity: EntityId,
+    },
+}
+
+impl PhysicsEvent {
+    pub fn targets(&self) -> (EventTarget, EventTarget) {
+        match self {
+            Self::ContactStarted { first, second }
+            | Self::ContactStopped { first, second, .. }
+            | Self::IntersectionStarted { first, second }
+            | Self::IntersectionStopped { first, second, .. }
+            | Self::ContactForce { first, second, .. } => (*first, *second),
+            Self::TriggerEntered { trigger, entity }
+            | Self::TriggerStayed { trigger, entity }
+            | Self::TriggerExited { trigger, entity } => {
+                (EventTarget::Trigger(*trigger), EventTarget::Entity(*entity))
+            }
+        }
+    }
+
+    /// Whether the given entity (or one of its hitboxes) is one of the two sides of the event.
+    pub fn involves(&self, entity_id: EntityId) -> bool {
+        let (first, second) = self.targets();
+        first.entity() == Some(entity_id) || second.entity() == Some(entity_id)
+    }
+}
+
+/// Channels rapier writes its events into while a single step runs.
+pub(crate) struct StepEvents {
+    pub collector: ChannelEventCollector,
+    collision_events: Receiver<CollisionEvent>,
+    contact_force_events: Receiver<ContactForceEvent>,
+}
+
+impl StepEvents {
+    pub fn new() -> Self {
+        let (collision_sender, collision_events) = unbounded();
+        let (contact_force_sender, contact_force_events) = unbounded();
+        Self {
+            collector: ChannelEventCollector::new(collision_sender, contact_force_sender),
+            collision_events,
+            contact_force_events,
+        }
+    }
+
+    /// Converts everything collected during the step, `target` resolves collider handles.
+    pub fn drain(self, target: impl Fn(ColliderHandle) -> EventTarget) -> Vec<PhysicsEvent> {
+        let mut events: Vec<PhysicsEvent> = self
+            .collision_events
+            .try_iter()
+            .map(|event| {
+                let first = target(event.collider1());
+                let second = target(event.collider2());
+                match event {
+                    CollisionEvent::Started(_, _, flags)
+                        if flags.contains(CollisionEventFlags::SENSOR) =>
+                    {
+                        PhysicsEvent::IntersectionStarted { first, second }
+                    }
+                    CollisionEvent::Started(..) => PhysicsEvent::ContactStarted { first, second },
+                    CollisionEvent::Stopped(_, _, flags)
+                        if flags.contains(CollisionEventFlags::SENSOR) =>
+                    {
+                        PhysicsEvent::IntersectionStopped {
+                            first,
+                            second,
+                            removed: flags.contains(CollisionEventFlags::REMOVED),
+                        }
+                    }
+                    CollisionEvent::Stopped(_, _, flags) => PhysicsEvent::ContactStopped {
+                        first,
+                        second,
+                        removed: flags.contains(CollisionEventFlags::REMOVED),
+                    },
+                }
+            })
+            .collect();
+
+        events.extend(self.contact_force_events.try_iter().map(|event| {
+            PhysicsEvent::ContactForce {
+                first: target(event.collider1),
+                second: target(event.collider2),
+                total_force_magnitude: event.total_force_magnitude,
+                max_force_direction: event.max_force_direction,
+            }
+        }));
+        events
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::{HashMap, VecDeque};
+
+use rapier3d::prelude::Isometry;
+
+use crate::entity::EntityId;
+use crate::stance::Stance;
+
+/// Number of ticks kept by default, one second at the 60 ticks per second rapier steps with.
+pub const DEFAULT_HISTORY_LENGTH: usize = 60;
+
+/// Where an entity was at the end of a tick, and how tall it was.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RecordedPose {
+    pub pose: Isometry<f32>,
+    pub stance: Stance,
+}
+
+/// Ring buffer with the poses and stances of every entity at the end of the last few ticks,
+/// used to rewind entities for lag-compensated hit detection.
+pub(crate) struct TransformHistory {
+    length: usize,
+    ticks: VecDeque<(u64, HashMap<EntityId, RecordedPose>)>,
+}
+
+impl TransformHistory {
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            ticks: VecDeque::with_capacity(length),
+        }
+    }
+
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length;
+        self.truncate();
+    }
+
+    pub fn record(&mut self, tick: u64, poses: HashMap<EntityId, RecordedPose>) {
+        self.ticks.push_back((tick, poses));
+        self.truncate();
+    }
+
+    pub fn at(&self, tick: u64) -> Option<&HashMap<EntityId, RecordedPose>> {
+        // Ticks are recorded in order, so the oldest one tells us where to look
+        let (oldest, _) = self.ticks.front()?;
+        let index = tick.checked_sub(*oldest)? as usize;
+        self.ticks
+            .get(index)
+            .filter(|(recorded, _)| *recorded == tick)
+            .map(|(_, poses)| poses)
+    }
+
+    /// Forgets the ticks after `tick`, e.g. the ones a rollback is going to simulate again.
+    pub fn discard_after(&mut self, tick: u64) {
+        while self
+            .ticks
+            .back()
+            .is_some_and(|(recorded, _)| *recorded > tick)
+        {
+            self.ticks.pop_back();
+        }
+    }
+
+    pub fn oldest_tick(&self) -> Option<u64> {
+        self.ticks.front().map(|(tick, _)| *tick)
+    }
+
+    fn truncate(&mut self) {
+        while self.ticks.len() > self.length {
+            self.ticks.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::TransformHistory;
+
+    #[test]
+    fn ring_buffer_test() {
+        let mut history = TransformHistory::new(3);
+        for tick in 1..=5 {
+            history.record(tick, HashMap::new());
+        }
+
+        assert_eq!(history.oldest_tick(), Some(3));
+        assert!(history.at(2).is_none());
+        assert!(history.at(3).is_some());
+        assert!(history.at(5).is_some());
+        assert!(history.at(6).is_none());
+
+        history.discard_after(3);
+        assert!(history.at(3).is_some());
+        assert!(history.at(4).is_none());
+        history.record(4, HashMap::new());
+        assert!(history.at(4).is_some());
+
+        history.set_length(1);
+        assert_eq!(history.oldest_tick(), Some(4));
+    }
+}
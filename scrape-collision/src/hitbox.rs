@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+use crate::shape::EntityShape;
+
+/// Which part of a player a hit landed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HitboxPart {
+    Head,
+    Torso,
+    Legs,
+}
+
+/// A hit volume attached to an entity, see
+/// [`GameCollider::attach_hitboxes`](crate::collider::GameCollider::attach_hitboxes).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hitbox {
+    pub part: HitboxPart,
+    pub shape: EntityShape,
+    /// Position relative to the entity's center, it turns with the entity
+    pub offset: [f32; 3],
+}
+
+impl Hitbox {
+    pub fn new(part: HitboxPart, shape: EntityShape, offset: [f32; 3]) -> Self {
+        Self {
+            part,
+            shape,
+            offset,
+        }
+    }
+
+    /// The hitbox squashed along the Y axis like the entity it is attached to, see
+    /// [`EntityShape::scaled_height`].
+    pub fn scaled_height(&self, scale: f32) -> Self {
+        let [x, y, z] = self.offset;
+        Self::new(
+            self.part,
+            self.shape.scaled_height(scale),
+            [x, y * scale, z],
+        )
+    }
+
+    /// Head, torso and legs stacked inside the default capsule, which is 1.4 units tall.
+    pub fn humanoid() -> Vec<Self> {
+        vec![
+            Self::new(
+                HitboxPart::Head,
+                EntityShape::Ball { radius: 0.15 },
+                [0.0, 0.5, 0.0],
+            ),
+            Self::new(
+                HitboxPart::Torso,
+                EntityShape::Cuboid {
+                    half_extents: [0.2, 0.25, 0.15],
+                },
+                [0.0, 0.1, 0.0],
+            ),
+            Self::new(
+                HitboxPart::Legs,
+                EntityShape::Cuboid {
+                    half_extents: [0.15, 0.275, 0.12],
+                },
+                [0.0, -0.425, 0.0],
+            ),
+        ]
+    }
+}
@@ -0,0 +1,250 @@
+use rapier3d::prelude::{Group, InteractionGroups};
+use serde::{Deserialize, Serialize};
+
+/// Named collision layers, each one is a bit of a rapier [`Group`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionLayer {
+    World,
+    Player,
+    Projectile,
+    Trigger,
+    Pickup,
+    /// Hit volumes of players, e.g. to tell headshots apart
+    Hitbox,
+    /// Team `0` up to [`MAX_TEAMS`] - 1
+    Team(u8),
+}
+
+/// Number of teams which fit into the groups next to the other layers.
+pub const MAX_TEAMS: u8 = 24;
+const FIRST_TEAM_BIT: u32 = 8;
+
+impl CollisionLayer {
+    pub fn group(self) -> Group {
+        let bit = match self {
+            Self::World => 0,
+            Self::Player => 1,
+            Self::Projectile => 2,
+            Self::Trigger => 3,
+            Self::Pickup => 4,
+            Self::Hitbox => 5,
+            Self::Team(team) => {
+                debug_assert!(team < MAX_TEAMS, "team {team} doesn't fit into the groups");
+                FIRST_TEAM_BIT + team as u32
+            }
+        };
+        Group::from_bits_truncate(1_u32.checked_shl(bit).unwrap_or(0))
+    }
+
+    fn all_teams() -> Group {
+        Group::from_bits_truncate(u32::MAX << FIRST_TEAM_BIT)
+    }
+}
+
+fn groups(layers: &[CollisionLayer]) -> Group {
+    layers
+        .iter()
+        .fold(Group::NONE, |group, layer| group | layer.group())
+}
+
+/// Which layers an entity is part of and which layers it interacts with.
+///
+/// Two colliders only interact if each one's filter contains one of the other's layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "RawCollisionLayers", into = "RawCollisionLayers")]
+pub struct CollisionLayers {
+    memberships: Group,
+    filter: Group,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RawCollisionLayers {
+    memberships: u32,
+    filter: u32,
+}
+
+impl From<RawCollisionLayers> for CollisionLayers {
+    fn from(raw: RawCollisionLayers) -> Self {
+        Self {
+            memberships: Group::from_bits_truncate(raw.memberships),
+            filter: Group::from_bits_truncate(raw.filter),
+        }
+    }
+}
+
+impl From<CollisionLayers> for RawCollisionLayers {
+    fn from(layers: CollisionLayers) -> Self {
+        Self {
+            memberships: layers.memberships.bits(),
+            filter: layers.filter.bits(),
+        }
+    }
+}
+
+impl Default for CollisionLayers {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl CollisionLayers {
+    pub fn new(memberships: &[CollisionLayer], filter: &[CollisionLayer]) -> Self {
+        Self {
+            memberships: groups(memberships),
+            filter: groups(filter),
+        }
+    }
+
+    /// Part of and interacting with every layer, what entities used before layers existed.
+    pub fn all() -> Self {
+        Self {
+            memberships: Group::ALL,
+            filter: Group::ALL,
+        }
+    }
+
+    /// The map, which everything can collide with.
+    pub fn world() -> Self {
+        Self {
+            memberships: CollisionLayer::World.group(),
+            filter: Group::ALL,
+        }
+    }
+
+    pub fn player(team: Option<u8>) -> Self {
+        let layers = Self::new(
+            &[CollisionLayer::Player],
+            &[
+                CollisionLayer::World,
+                CollisionLayer::Player,
+                CollisionLayer::Projectile,
+                CollisionLayer::Trigger,
+                CollisionLayer::Pickup,
+            ],
+        )
+        .with_filter_group(CollisionLayer::all_teams());
+
+        match team {
+            Some(team) => layers.with_membership(CollisionLayer::Team(team)),
+            None => layers,
+        }
+    }
+
+    /// Projectiles never hit each other. With a team they only hit players (and hitboxes) of other teams,
+    /// so players have to be part of a team as well.
+    pub fn projectile(team: Option<u8>) -> Self {
+        let layers = Self::new(
+            &[CollisionLayer::Projectile],
+            &[CollisionLayer::World, CollisionLayer::Trigger],
+        );
+
+        match team {
+            Some(team) => layers
+                .with_filter_group(CollisionLayer::all_teams())
+                .without_filter(CollisionLayer::Team(team)),
+            None => layers
+                .with_filter(CollisionLayer::Player)
+                .with_filter(CollisionLayer::Hitbox),
+        }
+    }
+
+    pub fn trigger() -> Self {
+        Self::new(
+            &[CollisionLayer::Trigger],
+            &[CollisionLayer::Player, CollisionLayer::Projectile],
+        )
+    }
+
+    pub fn pickup() -> Self {
+        Self::new(&[CollisionLayer::Pickup], &[CollisionLayer::Player])
+    }
+
+    /// Hitboxes of an entity on the `owner` layers: they keep its team and only projectiles interact with them.
+    pub fn hitbox(owner: CollisionLayers) -> Self {
+        Self {
+            memberships: CollisionLayer::Hitbox.group()
+                | (owner.memberships & CollisionLayer::all_teams()),
+            filter: CollisionLayer::Projectile.group(),
+        }
+    }
+
+    pub fn with_membership(mut self, layer: CollisionLayer) -> Self {
+        self.memberships |= layer.group();
+        self
+    }
+
+    pub fn with_filter(self, layer: CollisionLayer) -> Self {
+        self.with_filter_group(layer.group())
+    }
+
+    pub fn without_filter(mut self, layer: CollisionLayer) -> Self {
+        self.filter -= layer.group();
+        self
+    }
+
+    fn with_filter_group(mut self, group: Group) -> Self {
+        self.filter |= group;
+        self
+    }
+
+    pub fn contains(&self, layer: CollisionLayer) -> bool {
+        self.memberships.contains(layer.group())
+    }
+
+    pub fn interacts_with(&self, other: &Self) -> bool {
+        self.to_interaction_groups()
+            .test(other.to_interaction_groups())
+    }
+
+    pub fn to_interaction_groups(&self) -> InteractionGroups {
+        InteractionGroups::new(self.memberships, self.filter)
+    }
+
+    /// Groups used when moving, triggers and pickups are only overlapped and never block.
+    pub fn movement_groups(&self) -> InteractionGroups {
+        InteractionGroups::new(
+            self.memberships,
+            self.filter - CollisionLayer::Trigger.group() - CollisionLayer::Pickup.group(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollisionLayer, CollisionLayers};
+
+    #[test]
+    fn layer_rules_test() {
+        let world = CollisionLayers::world();
+        let player = CollisionLayers::player(None);
+        let bullet = CollisionLayers::projectile(None);
+
+        assert!(player.interacts_with(&world));
+        assert!(player.interacts_with(&player));
+        assert!(bullet.interacts_with(&player));
+        assert!(bullet.interacts_with(&world));
+        assert!(!bullet.interacts_with(&bullet));
+
+        let pickup = CollisionLayers::pickup();
+        assert!(player.interacts_with(&pickup));
+        assert!(!pickup.interacts_with(&bullet));
+        assert!(!player
+            .movement_groups()
+            .test(pickup.to_interaction_groups()));
+
+        let red = CollisionLayers::player(Some(0));
+        let blue = CollisionLayers::player(Some(1));
+        let red_bullet = CollisionLayers::projectile(Some(0));
+        assert!(red.contains(CollisionLayer::Team(0)));
+        assert!(!red_bullet.interacts_with(&red));
+        assert!(red_bullet.interacts_with(&blue));
+        assert!(red.interacts_with(&blue));
+
+        let red_head = CollisionLayers::hitbox(red);
+        assert!(!red_head.interacts_with(&blue));
+        assert!(!red_bullet.interacts_with(&red_head));
+        assert!(CollisionLayers::projectile(Some(1)).interacts_with(&red_head));
+        assert!(bullet.interacts_with(&CollisionLayers::hitbox(player)));
+    }
+}
@@ -1,5 +1,20 @@
 #![feature(iter_map_windows)]
 
+pub mod clock;
 pub mod collider;
-pub mod helpers;
+pub mod config;
+pub mod entity;
+pub mod errors;
+pub mod events;
+pub mod history;
+pub mod hitbox;
+pub mod layers;
+pub mod map;
+pub mod orientation;
+pub mod queries;
 pub mod rapier;
+pub mod shape;
+pub mod snapshot;
+pub mod stance;
+pub mod surface;
+pub mod triggers;
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use rapier3d::na::{Matrix4, Point3};
+use rapier3d::prelude::SharedShape;
+use scrape_gltf_loader::loader::stream_gltf_file;
+use scrape_gltf_loader::mesh::Mesh;
+
+use crate::shape::EntityShape;
+use crate::surface::MapSurface;
+
+/// A map trimesh and the source data we describe its hits with.
+pub(crate) struct MapPiece {
+    pub shape: SharedShape,
+    /// Index of the glTF mesh, what the map colliders are tagged with
+    pub mesh_index: usize,
+    pub surface: Arc<MapSurface>,
+}
+
+/// A trigger volume of the map, attached with the map colliders.
+pub(crate) struct MapTrigger {
+    /// Node or mesh name, starting with [`TRIGGER_PREFIX`]
+    pub name: String,
+    pub shape: SharedShape,
+}
+
+/// Name prefix of the meshes (or their nodes) which are trigger volumes instead of map geometry,
+/// see [`GameCollider::load_map_triggers`](crate::collider::GameCollider::load_map_triggers).
+pub const TRIGGER_PREFIX: &str = "trigger_";
+
+/// Map colliders built once from a glTF file, which any number of
+/// [`GameCollider`](crate::collider::GameCollider) worlds can attach.
+///
+/// The trimeshes (including their acceleration structures) and surface data are reference-counted,
+/// so attaching the map to another world only creates the colliders pointing at them. The same goes
+/// for the trigger volumes, the meshes named with [`TRIGGER_PREFIX`].
+pub struct MapGeometry {
+    pub(crate) pieces: Vec<MapPiece>,
+    pub(crate) triggers: Vec<MapTrigger>,
+}
+
+impl MapGeometry {
+    pub fn load(map_path: String) -> Arc<Self> {
+        let mut map_mesh = None;
+        let mut triggers = Vec::new();
+        for mesh in stream_gltf_file(map_path) {
+            match trigger_name(&mesh, TRIGGER_PREFIX) {
+                // Flat meshes have no convex hull, and couldn't contain anyone anyway
+                Some(name) => {
+                    if let Ok(shape) = trigger_hull(&mesh).to_shared_shape() {
+                        triggers.push(MapTrigger { name, shape });
+                    }
+                }
+                // Only take the first mesh since it will always be 1 big mesh,
+                // the trigger volumes next to it aren't walls
+                None if map_mesh.is_none() => map_mesh = Some(mesh),
+                None => {}
+            }
+        }
+        let mesh = map_mesh.expect("map file did not contain any meshes");
+
+        let transform = mesh_transform(&mesh);
+        let pieces = mesh
+            .mesh_collection()
+            .into_iter()
+            .map(|tri_mesh| {
+                let vertices = tri_mesh
+                    .vertices
+                    .iter()
+                    .map(|vertex| transform.transform_point(&Point3::from(*vertex)))
+                    .collect();
+                MapPiece {
+                    shape: SharedShape::trimesh(vertices, tri_mesh.indices.clone()),
+                    mesh_index: mesh.index,
+                    surface: Arc::new(MapSurface::new(&mesh, tri_mesh)),
+                }
+            })
+            .collect();
+
+        Arc::new(Self { pieces, triggers })
+    }
+
+    /// Number of trimesh colliders the map adds to a world.
+    pub fn collider_count(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Number of trigger volumes the map adds to a world.
+    pub fn trigger_count(&self) -> usize {
+        self.triggers.len()
+    }
+}
+
+/// The node or mesh name if either starts with `prefix`, preferring the node's.
+pub(crate) fn trigger_name(mesh: &Mesh, prefix: &str) -> Option<String> {
+    mesh.nodes
+        .first()
+        .and_then(|node| node.name.clone())
+        .filter(|name| name.starts_with(prefix))
+        .or_else(|| mesh.name.clone())
+        .filter(|name| name.starts_with(prefix))
+}
+
+/// World transform of the first node instancing the mesh, the frame map colliders are built in.
+pub(crate) fn mesh_transform(mesh: &Mesh) -> Matrix4<f32> {
+    mesh.nodes
+        .first()
+        .map_or_else(Matrix4::identity, |node| Matrix4::from(node.transform))
+}
+
+/// Convex hull of the mesh's vertices, placed with its node's transform like the map.
+pub(crate) fn trigger_hull(mesh: &Mesh) -> EntityShape {
+    let transform = mesh_transform(mesh);
+    let points = mesh
+        .mesh_collection()
+        .iter()
+        .flat_map(|tri_mesh| tri_mesh.vertices.iter())
+        .map(|vertex| {
+            let point = transform.transform_point(&Point3::from(*vertex));
+            [point.x, point.y, point.z]
+        })
+        .collect();
+    EntityShape::ConvexHull { points }
+}
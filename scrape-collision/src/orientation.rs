@@ -0,0 +1,150 @@
+//! Orientation helpers following the axis convention of the Godot client and glTF maps:
+//! right-handed, `+Y` is up, `+X` is right and entities look along `-Z`.
+
+use std::f32::consts::FRAC_PI_2;
+
+use rapier3d::na::{Quaternion, Unit, UnitQuaternion, Vector3};
+
+use crate::errors::CollisionError;
+
+/// Direction an entity looks at without any rotation.
+pub fn forward_axis() -> Vector3<f32> {
+    -Vector3::z()
+}
+
+pub fn right_axis() -> Vector3<f32> {
+    Vector3::x()
+}
+
+pub fn up_axis() -> Vector3<f32> {
+    Vector3::y()
+}
+
+/// Highest pitch we accept, slightly below straight up/down so yaw stays defined.
+pub const MAX_PITCH: f32 = FRAC_PI_2 - 0.001;
+
+/// Direction the rotation looks at, e.g. where a bullet has to fly.
+pub fn forward(rotation: &UnitQuaternion<f32>) -> Vector3<f32> {
+    rotation * forward_axis()
+}
+
+pub fn right(rotation: &UnitQuaternion<f32>) -> Vector3<f32> {
+    rotation * right_axis()
+}
+
+pub fn up(rotation: &UnitQuaternion<f32>) -> Vector3<f32> {
+    rotation * up_axis()
+}
+
+pub fn clamp_pitch(pitch: f32) -> f32 {
+    pitch.clamp(-MAX_PITCH, MAX_PITCH)
+}
+
+/// Builds a rotation from yaw around `+Y` (positive turns left) followed by pitch around the
+/// rotated `+X` axis (positive looks up), both in radians. The pitch is clamped with [`clamp_pitch`].
+pub fn from_yaw_pitch(yaw: f32, pitch: f32) -> UnitQuaternion<f32> {
+    let yaw = UnitQuaternion::from_axis_angle(&Unit::new_unchecked(up_axis()), yaw);
+    let pitch =
+        UnitQuaternion::from_axis_angle(&Unit::new_unchecked(right_axis()), clamp_pitch(pitch));
+    yaw * pitch
+}
+
+/// Yaw and pitch of the direction the rotation looks at, the inverse of [`from_yaw_pitch`] (roll is lost).
+pub fn yaw_pitch(rotation: &UnitQuaternion<f32>) -> (f32, f32) {
+    let forward = forward(rotation);
+    let yaw = (-forward.x).atan2(-forward.z);
+    let pitch = forward.y.clamp(-1.0, 1.0).asin();
+    (yaw, pitch)
+}
+
+/// Turns a client-supplied quaternion into a unit quaternion, rejecting NaN, infinite and zero-length ones.
+pub fn normalize_quaternion(
+    i: f32,
+    j: f32,
+    k: f32,
+    w: f32,
+) -> Result<UnitQuaternion<f32>, CollisionError> {
+    let quaternion = Quaternion::new(w, i, j, k);
+    if !quaternion
+        .coords
+        .iter()
+        .all(|component| component.is_finite())
+    {
+        return Err(CollisionError::InvalidRotation);
+    }
+
+    UnitQuaternion::try_new(quaternion, 1.0e-6).ok_or(CollisionError::InvalidRotation)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use rapier3d::na::{UnitQuaternion, Vector3};
+
+    use super::{
+        clamp_pitch, forward, from_yaw_pitch, normalize_quaternion, right, up, yaw_pitch, MAX_PITCH,
+    };
+    use crate::errors::CollisionError;
+
+    fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>) {
+        assert!(
+            (actual - expected).norm() < 1.0e-5,
+            "{actual:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn identity_axes_test() {
+        let identity = UnitQuaternion::identity();
+        assert_close(forward(&identity), Vector3::new(0.0, 0.0, -1.0));
+        assert_close(right(&identity), Vector3::new(1.0, 0.0, 0.0));
+        assert_close(up(&identity), Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn yaw_pitch_test() {
+        let left = from_yaw_pitch(FRAC_PI_2, 0.0);
+        assert_close(forward(&left), Vector3::new(-1.0, 0.0, 0.0));
+        assert_close(right(&left), Vector3::new(0.0, 0.0, -1.0));
+        assert_close(up(&left), Vector3::new(0.0, 1.0, 0.0));
+
+        let looking_up = from_yaw_pitch(0.0, FRAC_PI_4);
+        let half = FRAC_PI_4.sin();
+        assert_close(forward(&looking_up), Vector3::new(0.0, half, -half));
+        assert_close(right(&looking_up), Vector3::new(1.0, 0.0, 0.0));
+
+        let (yaw, pitch) = yaw_pitch(&from_yaw_pitch(-0.5, 0.3));
+        assert!((yaw + 0.5).abs() < 1.0e-5);
+        assert!((pitch - 0.3).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn clamp_pitch_test() {
+        assert_eq!(clamp_pitch(3.0), MAX_PITCH);
+        assert_eq!(clamp_pitch(-3.0), -MAX_PITCH);
+        let (_, pitch) = yaw_pitch(&from_yaw_pitch(0.0, 10.0));
+        assert!(pitch < FRAC_PI_2);
+    }
+
+    #[test]
+    fn normalize_quaternion_test() {
+        // 90 degrees around +Y, scaled up like an unnormalized client value
+        let half = FRAC_PI_4.sin();
+        let rotation = normalize_quaternion(0.0, 2.0 * half, 0.0, 2.0 * half).unwrap();
+        assert_close(forward(&rotation), Vector3::new(-1.0, 0.0, 0.0));
+
+        assert_eq!(
+            normalize_quaternion(0.0, 0.0, 0.0, 0.0),
+            Err(CollisionError::InvalidRotation)
+        );
+        assert_eq!(
+            normalize_quaternion(f32::NAN, 0.0, 0.0, 1.0),
+            Err(CollisionError::InvalidRotation)
+        );
+        assert_eq!(
+            normalize_quaternion(0.0, f32::INFINITY, 0.0, 1.0),
+            Err(CollisionError::InvalidRotation)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use rapier3d::prelude::{ColliderHandle, Point, QueryFilter, Vector};
+use serde::{Deserialize, Serialize};
+
+use crate::entity::EntityId;
+use crate::events::EventTarget;
+use crate::layers::CollisionLayers;
+
+/// Decides which colliders a query like [`GameCollider::raycast`](crate::collider::GameCollider::raycast) can hit.
+#[derive(Debug, Clone)]
+pub struct HitFilter {
+    pub exclude_entities: Vec<EntityId>,
+    pub exclude_colliders: Vec<ColliderHandle>,
+    pub hit_map: bool,
+    pub hit_entities: bool,
+    pub hit_triggers: bool,
+    /// Only hits colliders these layers interact with
+    pub layers: Option<CollisionLayers>,
+}
+
+impl Default for HitFilter {
+    fn default() -> Self {
+        Self {
+            exclude_entities: Vec::new(),
+            exclude_colliders: Vec::new(),
+            hit_map: true,
+            hit_entities: true,
+            hit_triggers: false,
+            layers: None,
+        }
+    }
+}
+
+impl HitFilter {
+    /// Skips the given entity, e.g. the shooter of a hitscan weapon.
+    pub fn exclude_entity(mut self, entity_id: EntityId) -> Self {
+        self.exclude_entities.push(entity_id);
+        self
+    }
+
+    pub fn exclude_collider(mut self, collider: ColliderHandle) -> Self {
+        self.exclude_colliders.push(collider);
+        self
+    }
+
+    /// Hits what an entity on these layers would, e.g. a hitscan weapon uses [`CollisionLayers::projectile`].
+    pub fn layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = Some(layers);
+        self
+    }
+
+    /// Only hits the map, useful for line-of-sight checks.
+    pub fn map_only() -> Self {
+        Self {
+            hit_entities: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn entities_only() -> Self {
+        Self {
+            hit_map: false,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn apply<'a>(&self, filter: QueryFilter<'a>) -> QueryFilter<'a> {
+        match self.layers {
+            Some(layers) => filter.groups(layers.to_interaction_groups()),
+            None => filter,
+        }
+    }
+
+    pub(crate) fn accepts(&self, handle: ColliderHandle, target: EventTarget) -> bool {
+        if self.exclude_colliders.contains(&handle) {
+            return false;
+        }
+
+        match target {
+            EventTarget::Entity(entity_id)
+            | EventTarget::Hitbox {
+                entity: entity_id, ..
+            } => self.hit_entities && !self.exclude_entities.contains(&entity_id),
+            EventTarget::Map(_) => self.hit_map,
+            EventTarget::Trigger(_) => self.hit_triggers,
+            EventTarget::Collider(_) => true,
+        }
+    }
+}
+
+/// First (or every) collider hit by a ray or shape cast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryHit {
+    pub target: EventTarget,
+    /// World-space point of impact
+    pub point: Point<f32>,
+    /// World-space surface normal at the impact, pointing away from the hit collider
+    pub normal: Vector<f32>,
+    /// Distance travelled along the direction until the impact, `0.0` if the query started inside the collider
+    pub distance: f32,
+    /// Hit triangle of a map collider, see [`GameCollider::describe_hit`](crate::collider::GameCollider::describe_hit)
+    pub triangle: Option<u32>,
+}
+
+/// An entity within the sphere of [`GameCollider::overlap_sphere`](crate::collider::GameCollider::overlap_sphere).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AreaHit {
+    pub entity_id: EntityId,
+    /// Point of the entity's collider closest to the center
+    pub point: Point<f32>,
+    /// Distance from the center to `point`, `0.0` if the center is inside the entity
+    pub distance: f32,
+    /// Unit vector from the center towards the entity's center, e.g. to push it away
+    pub direction: Vector<f32>,
+}
+
+/// How splash damage or knockback fades between the center of an explosion and its radius.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Falloff {
+    /// Full amount everywhere within the radius
+    Constant,
+    Linear,
+    /// Drops off quickly, only entities close to the center take most of it
+    Quadratic,
+}
+
+impl Falloff {
+    /// Share of the full amount at `distance`, from `1.0` at the center down to `0.0` at `radius`.
+    pub fn scale(&self, distance: f32, radius: f32) -> f32 {
+        if distance > radius || radius <= 0.0 {
+            return 0.0;
+        }
+
+        let remaining = 1.0 - distance / radius;
+        match self {
+            Self::Constant => 1.0,
+            Self::Linear => remaining,
+            Self::Quadratic => remaining * remaining,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Falloff;
+
+    #[test]
+    fn falloff_test() {
+        assert_eq!(Falloff::Constant.scale(4.0, 5.0), 1.0);
+        assert_eq!(Falloff::Linear.scale(0.0, 5.0), 1.0);
+        assert!((Falloff::Linear.scale(2.5, 5.0) - 0.5).abs() < 1.0e-6);
+        assert!((Falloff::Quadratic.scale(2.5, 5.0) - 0.25).abs() < 1.0e-6);
+        assert_eq!(Falloff::Linear.scale(6.0, 5.0), 0.0);
+        assert_eq!(Falloff::Constant.scale(0.0, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,81 @@
+use rapier3d::{na::Point3, prelude::SharedShape};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::CollisionError;
+
+/// The collider shape an entity is loaded with.
+///
+/// The same shape is used when sweeping the entity in
+/// [`GameCollider::calculate_movement`](crate::collider::GameCollider::calculate_movement).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityShape {
+    /// Capsule standing along the Y axis
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    Ball {
+        radius: f32,
+    },
+    Cuboid {
+        half_extents: [f32; 3],
+    },
+    /// Convex hull around the given points
+    ConvexHull {
+        points: Vec<[f32; 3]>,
+    },
+}
+
+impl EntityShape {
+    pub fn to_shared_shape(&self) -> Result<SharedShape, CollisionError> {
+        match self {
+            EntityShape::Capsule {
+                half_height,
+                radius,
+            } => Ok(SharedShape::capsule_y(*half_height, *radius)),
+            EntityShape::Ball { radius } => Ok(SharedShape::ball(*radius)),
+            EntityShape::Cuboid { half_extents } => Ok(SharedShape::cuboid(
+                half_extents[0],
+                half_extents[1],
+                half_extents[2],
+            )),
+            EntityShape::ConvexHull { points } => {
+                let points: Vec<Point3<f32>> = points.iter().copied().map(Point3::from).collect();
+                SharedShape::convex_hull(&points).ok_or(CollisionError::InvalidShape)
+            }
+        }
+    }
+
+    /// Scales the height along the Y axis around the center, e.g. for crouching.
+    ///
+    /// Capsules keep their radius, so they can't get shorter than a ball. Balls don't change.
+    pub fn scaled_height(&self, scale: f32) -> EntityShape {
+        match self {
+            EntityShape::Capsule {
+                half_height,
+                radius,
+            } => EntityShape::Capsule {
+                half_height: ((half_height + radius) * scale - radius).max(0.0),
+                radius: *radius,
+            },
+            EntityShape::Ball { radius } => EntityShape::Ball { radius: *radius },
+            EntityShape::Cuboid { half_extents } => EntityShape::Cuboid {
+                half_extents: [half_extents[0], half_extents[1] * scale, half_extents[2]],
+            },
+            EntityShape::ConvexHull { points } => EntityShape::ConvexHull {
+                points: points.iter().map(|[x, y, z]| [*x, y * scale, *z]).collect(),
+            },
+        }
+    }
+}
+
+impl Default for EntityShape {
+    /// The player-sized capsule every entity used before shapes were configurable.
+    fn default() -> Self {
+        EntityShape::Capsule {
+            half_height: 0.5,
+            radius: 0.2,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{DynamicBody, EntityId, EntityTag};
+use crate::hitbox::Hitbox;
+use crate::layers::CollisionLayers;
+use crate::shape::EntityShape;
+use crate::stance::Stance;
+use crate::triggers::TriggerId;
+
+/// Everything that changes while the game runs, taken with
+/// [`GameCollider::snapshot`](crate::collider::GameCollider::snapshot).
+///
+/// The map and the triggers are static and not part of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    /// [`GameCollider::current_tick`](crate::collider::GameCollider::current_tick) when it was taken
+    #[serde(default)]
+    pub tick: u64,
+    pub gravity: Vec<f32>,
+    pub entities: Vec<EntitySnapshot>,
+    pub trigger_occupants: Vec<(TriggerId, Vec<EntityId>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    pub entity_id: EntityId,
+    pub translation: [f32; 3],
+    /// Quaternion as `[i, j, k, w]`
+    pub rotation: [f32; 4],
+    pub shape: EntityShape,
+    pub layers: CollisionLayers,
+    #[serde(default)]
+    pub tag: Option<EntityTag>,
+    pub controller_profile: Option<String>,
+    pub vertical_velocity: f32,
+    pub grounded: bool,
+    pub gravity_scale: f32,
+    #[serde(default)]
+    pub dynamic: Option<DynamicBody>,
+    #[serde(default)]
+    pub linear_velocity: [f32; 3],
+    #[serde(default)]
+    pub angular_velocity: [f32; 3],
+    #[serde(default)]
+    pub hitboxes: Vec<Hitbox>,
+    #[serde(default)]
+    pub stance: Stance,
+}
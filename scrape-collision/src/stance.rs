@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// How an entity holds itself, set with
+/// [`GameCollider::set_entity_stance`](crate::collider::GameCollider::set_entity_stance).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stance {
+    #[default]
+    Standing,
+    Crouching,
+}
+
+impl Stance {
+    /// Share of the loaded (standing) height the entity and its hitboxes keep in this stance.
+    pub fn height_scale(&self) -> f32 {
+        match self {
+            Stance::Standing => 1.0,
+            Stance::Crouching => 0.6,
+        }
+    }
+}
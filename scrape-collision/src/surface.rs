@@ -0,0 +1,75 @@
+use rapier3d::na::Point3;
+use scrape_gltf_loader::mesh::{Mesh, SubTriMesh, VertexSample};
+
+/// Where a map trimesh collider was loaded from in the glTF file.
+#[derive(Debug, Clone)]
+pub struct SurfaceSource {
+    pub mesh_index: usize,
+    pub mesh_name: Option<String>,
+    pub primitive_index: usize,
+    pub node_name: Option<String>,
+    pub material_index: Option<usize>,
+    pub material_name: Option<String>,
+}
+
+/// Everything we know about the map triangle which was hit.
+#[derive(Debug, Clone)]
+pub struct HitDescription {
+    pub source: SurfaceSource,
+    pub triangle: u32,
+    /// Positions of the triangle corners, as loaded from the file
+    pub vertices: [Point3<f32>; 3],
+    /// Colour and UV channels of each corner
+    pub corners: [VertexSample; 3],
+    /// Channels interpolated at the hit point, if one was provided
+    pub sample: Option<VertexSample>,
+}
+
+/// The source data we retain for every map trimesh collider.
+pub(crate) struct MapSurface {
+    pub source: SurfaceSource,
+    pub tri_mesh: SubTriMesh,
+}
+
+impl MapSurface {
+    pub fn new(mesh: &Mesh, tri_mesh: SubTriMesh) -> Self {
+        let primitive = &mesh.mesh_primitives[tri_mesh.primitive_index];
+        Self {
+            source: SurfaceSource {
+                mesh_index: mesh.index,
+                mesh_name: mesh.name.clone(),
+                primitive_index: tri_mesh.primitive_index,
+                node_name: mesh.nodes.first().and_then(|node| node.name.clone()),
+                material_index: primitive.material_index,
+                material_name: primitive.material_name.clone(),
+            },
+            tri_mesh,
+        }
+    }
+
+    /// Describes `triangle`, optionally interpolating the channels with `barycentric`.
+    ///
+    /// Back-face ids reported by parry (offset by the triangle count) are folded back.
+    pub fn describe(&self, triangle: u32, barycentric: Option<[f32; 3]>) -> Option<HitDescription> {
+        let triangle_count = self.tri_mesh.indices.len();
+        if triangle_count == 0 {
+            return None;
+        }
+
+        let triangle = triangle as usize % triangle_count;
+        let indices = self.tri_mesh.indices[triangle];
+        let corner_weights = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+        Some(HitDescription {
+            source: self.source.clone(),
+            triangle: triangle as u32,
+            vertices: indices.map(|index| Point3::from(self.tri_mesh.vertices[index as usize])),
+            corners: corner_weights.map(|weights| {
+                self.tri_mesh
+                    .interpolate(triangle, weights)
+                    .unwrap_or_default()
+            }),
+            sample: barycentric.and_then(|weights| self.tri_mesh.interpolate(triangle, weights)),
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashSet;
+
+use rapier3d::prelude::ColliderHandle;
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{EntityId, EntityKind, EntityTag};
+
+/// Sensor volume created by [`GameCollider::add_trigger`](crate::collider::GameCollider::add_trigger).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "(u32, u32)", into = "(u32, u32)")]
+pub struct TriggerId(pub(crate) ColliderHandle);
+
+impl From<(u32, u32)> for TriggerId {
+    fn from((index, generation): (u32, u32)) -> Self {
+        Self(ColliderHandle::from_raw_parts(index, generation))
+    }
+}
+
+impl From<TriggerId> for (u32, u32) {
+    fn from(trigger: TriggerId) -> Self {
+        trigger.0.into_raw_parts()
+    }
+}
+
+impl TriggerId {
+    pub fn collider(&self) -> ColliderHandle {
+        self.0
+    }
+
+    /// Tag of the trigger's collider, its id packs the handle's index and generation.
+    pub fn to_tag(&self) -> EntityTag {
+        let (index, generation) = self.0.into_raw_parts();
+        EntityTag::new(
+            EntityKind::Trigger,
+            (index as u64) << 32 | generation as u64,
+        )
+    }
+
+    pub fn from_tag(tag: EntityTag) -> Option<Self> {
+        (tag.kind == EntityKind::Trigger)
+            .then(|| Self::from(((tag.id >> 32) as u32, tag.id as u32)))
+    }
+}
+
+/// A trigger and the entities which are currently inside of it.
+pub(crate) struct TriggerVolume {
+    pub name: String,
+    pub occupants: HashSet<EntityId>,
+}
+
+impl TriggerVolume {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            occupants: HashSet::new(),
+        }
+    }
+
+    /// Removes the occupant using `collider`, which can't be resolved to an entity anymore once it was unloaded.
+    pub fn remove_collider(&mut self, collider: ColliderHandle) -> Option<EntityId> {
+        let entity_id = *self
+            .occupants
+            .iter()
+            .find(|entity_id| entity_id.collider() == collider)?;
+        self.occupants.remove(&entity_id);
+        Some(entity_id)
+    }
+}
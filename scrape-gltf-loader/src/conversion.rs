@@ -10,6 +10,7 @@ use super::vertex_iterator::VertexAttributeIter;
 
 enum ConversionMode {
     Any,
+    Rgba,
     TexCoord,
 }
 
@@ -28,11 +29,9 @@ pub fn convert_attribute(
         gltf::Semantic::Tangents => {
             Some((AttributeMetadata::ATTRIBUTE_TANGENT, ConversionMode::Any))
         }
-        gltf::Semantic::TexCoords(0) => {
-            Some((AttributeMetadata::ATTRIBUTE_UV_0, ConversionMode::TexCoord))
-        }
-        gltf::Semantic::TexCoords(1) => {
-            Some((AttributeMetadata::ATTRIBUTE_UV_1, ConversionMode::TexCoord))
+        gltf::Semantic::Colors(set) => Some((AttributeMetadata::color(*set), ConversionMode::Rgba)),
+        gltf::Semantic::TexCoords(set) => {
+            Some((AttributeMetadata::uv(*set), ConversionMode::TexCoord))
         }
         // gltf::Semantic::Extras(name) => custom_vertex_attributes
         //    .get(name)
@@ -44,6 +43,7 @@ pub fn convert_attribute(
         let raw_iter = VertexAttributeIter::from_accessor(accessor.clone(), buffer_data);
         let converted_values = raw_iter.and_then(|iter| match conversion {
             ConversionMode::Any => iter.into_any_values(),
+            ConversionMode::Rgba => iter.into_rgba_values(),
             ConversionMode::TexCoord => iter.into_tex_coord_values(),
         });
 
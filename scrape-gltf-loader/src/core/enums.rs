@@ -1,4 +1,5 @@
 #[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub enum PrimitiveTopology {
     PointList = 0,
@@ -9,6 +10,7 @@ pub enum PrimitiveTopology {
 }
 
 #[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, PartialOrd, Ord, Eq)]
 pub enum AttributeType {
     Position = 0,
@@ -16,4 +18,7 @@ pub enum AttributeType {
     Uv0 = 2,
     Uv1 = 3,
     Tangent = 4,
+    Color = 5,
+    /// Any texture coordinate set past `TEXCOORD_1`
+    Uv = 6,
 }
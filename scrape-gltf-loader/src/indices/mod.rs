@@ -1,5 +1,6 @@
 use std::iter::FusedIterator;
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub enum IndexVec {
     U16(Vec<u16>),
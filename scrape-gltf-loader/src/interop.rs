@@ -0,0 +1,72 @@
+//! Conversions from the raw `[f32; 3]` data of the loader into the math libraries our consumers use.
+//!
+//! The generic helpers work with any type implementing `From<[f32; 3]>`, while the `glam`, `nalgebra`
+//! and `mint` features add named shortcuts and re-export the matching crate version.
+
+#[cfg(feature = "glam")]
+pub use glam;
+#[cfg(feature = "mint")]
+pub use mint;
+#[cfg(feature = "nalgebra")]
+pub use nalgebra;
+
+use crate::mesh::{attributes::attribute_data::AttributeData, SubTriMesh};
+
+impl SubTriMesh {
+    /// Converts the vertices into any point/vector type which can be built from `[f32; 3]`.
+    pub fn vertices_as<T: From<[f32; 3]>>(&self) -> Vec<T> {
+        self.vertices.iter().copied().map(T::from).collect()
+    }
+
+    #[cfg(feature = "glam")]
+    pub fn glam_vertices(&self) -> Vec<glam::Vec3> {
+        self.vertices_as()
+    }
+
+    #[cfg(feature = "nalgebra")]
+    pub fn nalgebra_vertices(&self) -> Vec<nalgebra::Point3<f32>> {
+        self.vertices_as()
+    }
+
+    #[cfg(feature = "mint")]
+    pub fn mint_vertices(&self) -> Vec<mint::Point3<f32>> {
+        self.vertices_as()
+    }
+}
+
+impl AttributeData {
+    /// Converts float triples (positions, normals) into any type which can be built from `[f32; 3]`.
+    ///
+    /// Returns `None` if the values are not of type `float3`.
+    pub fn float3_as<T: From<[f32; 3]>>(&self) -> Option<Vec<T>> {
+        self.as_float3()
+            .map(|values| values.iter().copied().map(T::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::Vec3;
+
+    use crate::mesh::{attributes::attribute_data::AttributeData, SubTriMesh};
+
+    #[test]
+    fn test_vertices_as() {
+        let tri_mesh = SubTriMesh {
+            primitive_index: 0,
+            vertices: vec![[0.0, 1.0, 2.0], [3.0, 4.0, 5.0]],
+            indices: vec![[0, 1, 0]],
+            colors: Default::default(),
+            uvs: Default::default(),
+        };
+
+        let vertices: Vec<Vec3> = tri_mesh.vertices_as();
+        assert_eq!(
+            vertices,
+            vec![Vec3::new(0.0, 1.0, 2.0), Vec3::new(3.0, 4.0, 5.0)]
+        );
+
+        let uvs = AttributeData::Float32x2(vec![[0.0, 1.0]]);
+        assert!(uvs.float3_as::<Vec3>().is_none());
+    }
+}
@@ -3,6 +3,7 @@
 pub mod conversion;
 pub mod core;
 pub mod indices;
+pub mod interop;
 pub mod loader;
 pub mod mesh;
 pub mod vertex_iterator;
@@ -1,85 +1,306 @@
-use gltf::{mesh::util::ReadIndices, Semantic};
+use std::collections::HashMap;
+
+use bevy_math::Mat4;
+use gltf::{buffer::Data, mesh::util::ReadIndices, Semantic};
 
 use crate::conversion;
 use crate::core::{enums, utility};
 use crate::indices::IndexVec;
-use crate::mesh::{attributes::*, mesh_primitive::MeshPrimitive, Mesh};
+use crate::mesh::{
+    attributes::*,
+    markers::{LinePath, MarkerSet},
+    mesh_primitive::MeshPrimitive,
+    Mesh, MeshNode,
+};
 
-// "./blend-files/Envoirment.gltf"
-pub fn load_gltf_file(file_path: String) -> Vec<Mesh> {
+/// Lazily decodes the meshes of an imported glTF file, one [`Mesh`] per `next` call.
+pub struct MeshIter {
+    document: gltf::Document,
+    buffers: Vec<Data>,
+    /// Nodes instancing each mesh, gathered in one pass over the nodes
+    mesh_nodes: HashMap<usize, Vec<MeshNode>>,
+    /// Meshes left to decode
+    remaining: std::ops::Range<usize>,
+}
+
+impl Iterator for MeshIter {
+    type Item = Mesh;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.remaining.next()?;
+        let mesh = mesh_at(&self.document, index)?;
+        let nodes = self.mesh_nodes.remove(&index).unwrap_or_default();
+        Some(load_mesh(mesh, nodes, &self.buffers))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+impl ExactSizeIterator for MeshIter {}
+
+/// Lazily decodes every primitive of an imported glTF file, mesh by mesh.
+///
+/// Yields `(mesh_index, primitive_index, primitive)` so callers can regroup them if needed.
+pub struct PrimitiveIter {
+    document: gltf::Document,
+    buffers: Vec<Data>,
+    /// `(mesh_index, primitive_index)` of the primitives left to decode, listed once up front
+    remaining: std::vec::IntoIter<(usize, usize)>,
+}
+
+impl Iterator for PrimitiveIter {
+    type Item = (usize, usize, MeshPrimitive);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (mesh_index, primitive_index) = self.remaining.next()?;
+        let primitive = mesh_at(&self.document, mesh_index)?
+            .primitives()
+            .nth(primitive_index)?;
+        Some((
+            mesh_index,
+            primitive_index,
+            load_primitive(primitive, &self.buffers),
+        ))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.remaining.size_hint()
+    }
+}
+
+/// The mesh at `index`, the glTF iterators index straight into the mesh (and primitive) arrays.
+fn mesh_at(document: &gltf::Document, index: usize) -> Option<gltf::Mesh<'_>> {
+    document.meshes().nth(index)
+}
+
+fn import_gltf_file(file_path: String) -> (gltf::Document, Vec<Data>) {
     let (document, buffers, _images) =
         gltf::import(file_path).expect("Couldn't read provided file_path");
-    let mut meshes = Vec::new();
-    for mesh in document.meshes() {
-        let mut mesh_primitives = Vec::new();
+    (document, buffers)
+}
 
-        for primitive in mesh.primitives() {
-            let primitive_topology = utility::get_primitive_topology(primitive.mode()).unwrap();
+// "./blend-files/Envoirment.gltf"
+/// Loads every mesh of the file.
+///
+/// With the `rayon` feature enabled, primitives are decoded in parallel.
+pub fn load_gltf_file(file_path: String) -> Vec<Mesh> {
+    #[cfg(feature = "rayon")]
+    return par_load_gltf_file(file_path);
 
-            let mut mesh_primitive = MeshPrimitive::new(primitive_topology);
-            for (semantic, accessor) in primitive.attributes() {
-                if [Semantic::Joints(0), Semantic::Weights(0)].contains(&semantic) {
-                    continue;
-                }
+    #[cfg(not(feature = "rayon"))]
+    return stream_gltf_file(file_path).collect();
+}
 
-                match conversion::convert_attribute(semantic, accessor, &buffers, None) {
-                    Ok((attribute, values)) => mesh_primitive.insert_attribute(attribute, values),
-                    Err(_err) => eprintln!("Something went wrong with adding the attribute..."),
-                }
-            }
+/// Imports the file and returns an iterator which decodes the meshes on demand,
+/// so only one decoded mesh has to be alive at a time.
+pub fn stream_gltf_file(file_path: String) -> MeshIter {
+    let (document, buffers) = import_gltf_file(file_path);
+    MeshIter {
+        mesh_nodes: mesh_nodes(&document),
+        remaining: 0..document.meshes().len(),
+        document,
+        buffers,
+    }
+}
 
-            let reader = primitive.reader(|buffer| Some(buffers[buffer.index()].0.as_slice()));
-
-            if let Some(indices) = reader.read_indices() {
-                mesh_primitive.set_indices(Some(match indices {
-                    ReadIndices::U8(is) => IndexVec::U16(is.map(|x| x as u16).collect()),
-                    ReadIndices::U16(is) => IndexVec::U16(is.collect()),
-                    ReadIndices::U32(is) => IndexVec::U32(is.collect()),
-                }));
-            };
-
-            if mesh_primitive
-                .attribute(attribute_metadata::AttributeMetadata::ATTRIBUTE_NORMAL)
-                .is_none()
-                && matches!(
-                    mesh_primitive.topology.clone(),
-                    enums::PrimitiveTopology::TriangleList
-                )
-            {
-                let vertex_count_before = mesh_primitive.count_vertices();
-                mesh_primitive.duplicate_vertices();
-                mesh_primitive.compute_flat_normals();
-                let vertex_count_after = mesh_primitive.count_vertices();
-
-                if vertex_count_before != vertex_count_after {
-                    println!("Missing vertex normals in indexed geometry, computing them as flat. Vertex count increased from {} to {}", vertex_count_before, vertex_count_after);
-                } else {
-                    println!("Missing vertex normals in indexed geometry, computing them as flat.");
-                }
-            }
+/// Point and line primitives of a file, see [`load_gltf_markers`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GltfMarkers {
+    pub marker_sets: Vec<MarkerSet>,
+    pub line_paths: Vec<LinePath>,
+}
 
-            if let Some(vertex_attribute) = reader
-                .read_tangents()
-                .map(|v| attribute_data::AttributeData::Float32x4(v.collect()))
-            {
-                mesh_primitive.insert_attribute(
-                    attribute_metadata::AttributeMetadata::ATTRIBUTE_TANGENT,
-                    vertex_attribute,
-                );
-            }
+/// Loads only the point clouds (spawn/pickup markers) and lines (patrol paths, rails,
+/// zone boundaries) of the file, in world space.
+pub fn load_gltf_markers(file_path: String) -> GltfMarkers {
+    let mut markers = GltfMarkers::default();
+    for mesh in stream_gltf_file(file_path) {
+        markers.marker_sets.extend(mesh.marker_sets());
+        markers.line_paths.extend(mesh.line_paths());
+    }
+
+    markers
+}
+
+/// Same as [`stream_gltf_file`], but yields the individual primitives instead of whole meshes.
+pub fn stream_gltf_primitives(file_path: String) -> PrimitiveIter {
+    let (document, buffers) = import_gltf_file(file_path);
+    let remaining: Vec<(usize, usize)> = document
+        .meshes()
+        .flat_map(|mesh| {
+            let mesh_index = mesh.index();
+            mesh.primitives()
+                .map(move |primitive| (mesh_index, primitive.index()))
+        })
+        .collect();
+    PrimitiveIter {
+        document,
+        buffers,
+        remaining: remaining.into_iter(),
+    }
+}
+
+/// Loads every mesh of the file, decoding all primitives in parallel on the rayon thread pool.
+#[cfg(feature = "rayon")]
+pub fn par_load_gltf_file(file_path: String) -> Vec<Mesh> {
+    use rayon::prelude::*;
 
-            mesh_primitives.push(mesh_primitive);
+    let (document, buffers) = import_gltf_file(file_path);
+    let mut nodes = mesh_nodes(&document);
+    let primitives: Vec<(usize, gltf::Primitive)> = document
+        .meshes()
+        .flat_map(|mesh| {
+            let mesh_index = mesh.index();
+            mesh.primitives()
+                .map(move |primitive| (mesh_index, primitive))
+        })
+        .collect();
+
+    let decoded: Vec<(usize, MeshPrimitive)> = primitives
+        .into_par_iter()
+        .map(|(mesh_index, primitive)| (mesh_index, load_primitive(primitive, &buffers)))
+        .collect();
+
+    let mut meshes: Vec<Mesh> = document
+        .meshes()
+        .map(|mesh| Mesh {
+            index: mesh.index(),
+            name: mesh.name().map(String::from),
+            nodes: nodes.remove(&mesh.index()).unwrap_or_default(),
+            mesh_primitives: Vec::new(),
+        })
+        .collect();
+    for (mesh_index, primitive) in decoded {
+        meshes[mesh_index].mesh_primitives.push(primitive);
+    }
+
+    meshes
+}
+
+fn load_mesh(mesh: gltf::Mesh, nodes: Vec<MeshNode>, buffers: &Vec<Data>) -> Mesh {
+    let mesh_primitives = mesh
+        .primitives()
+        .map(|primitive| load_primitive(primitive, buffers))
+        .collect();
+
+    Mesh {
+        index: mesh.index(),
+        name: mesh.name().map(String::from),
+        nodes,
+        mesh_primitives,
+    }
+}
+
+/// The nodes which instantiate each mesh, keyed by mesh index.
+fn mesh_nodes(document: &gltf::Document) -> HashMap<usize, Vec<MeshNode>> {
+    let node_transforms = node_transforms(document);
+    let mut nodes: HashMap<usize, Vec<MeshNode>> = HashMap::new();
+    for node in document.nodes() {
+        let Some(mesh) = node.mesh() else {
+            continue;
+        };
+        let transform = node_transforms
+            .get(&node.index())
+            .copied()
+            .unwrap_or_else(|| Mat4::from_cols_array_2d(&node.transform().matrix()));
+        nodes.entry(mesh.index()).or_default().push(MeshNode {
+            index: node.index(),
+            name: node.name().map(String::from),
+            transform: transform.to_cols_array_2d(),
+        });
+    }
+
+    nodes
+}
+
+/// Computes the world transform of every node reachable from a scene.
+fn node_transforms(document: &gltf::Document) -> HashMap<usize, Mat4> {
+    fn visit(node: gltf::Node, parent: Mat4, transforms: &mut HashMap<usize, Mat4>) {
+        let world = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+        transforms.insert(node.index(), world);
+        for child in node.children() {
+            visit(child, world, transforms);
+        }
+    }
+
+    let mut transforms = HashMap::new();
+    for scene in document.scenes() {
+        for node in scene.nodes() {
+            visit(node, Mat4::IDENTITY, &mut transforms);
+        }
+    }
+
+    transforms
+}
+
+fn load_primitive(primitive: gltf::Primitive, buffers: &Vec<Data>) -> MeshPrimitive {
+    let primitive_topology = utility::get_primitive_topology(primitive.mode()).unwrap();
+
+    let mut mesh_primitive = MeshPrimitive::new(primitive_topology);
+    let material = primitive.material();
+    mesh_primitive.material_index = material.index();
+    mesh_primitive.material_name = material.name().map(String::from);
+
+    for (semantic, accessor) in primitive.attributes() {
+        if [Semantic::Joints(0), Semantic::Weights(0)].contains(&semantic) {
+            continue;
         }
 
-        meshes.push(Mesh { mesh_primitives });
+        match conversion::convert_attribute(semantic, accessor, buffers, None) {
+            Ok((attribute, values)) => mesh_primitive.insert_attribute(attribute, values),
+            Err(_err) => eprintln!("Something went wrong with adding the attribute..."),
+        }
     }
 
-    return meshes;
+    let reader = primitive.reader(|buffer| Some(buffers[buffer.index()].0.as_slice()));
+
+    if let Some(indices) = reader.read_indices() {
+        mesh_primitive.set_indices(Some(match indices {
+            ReadIndices::U8(is) => IndexVec::U16(is.map(|x| x as u16).collect()),
+            ReadIndices::U16(is) => IndexVec::U16(is.collect()),
+            ReadIndices::U32(is) => IndexVec::U32(is.collect()),
+        }));
+    };
+
+    if mesh_primitive
+        .attribute(attribute_metadata::AttributeMetadata::ATTRIBUTE_NORMAL)
+        .is_none()
+        && matches!(
+            mesh_primitive.topology.clone(),
+            enums::PrimitiveTopology::TriangleList
+        )
+    {
+        let vertex_count_before = mesh_primitive.count_vertices();
+        mesh_primitive.duplicate_vertices();
+        mesh_primitive.compute_flat_normals();
+        let vertex_count_after = mesh_primitive.count_vertices();
+
+        if vertex_count_before != vertex_count_after {
+            println!("Missing vertex normals in indexed geometry, computing them as flat. Vertex count increased from {} to {}", vertex_count_before, vertex_count_after);
+        } else {
+            println!("Missing vertex normals in indexed geometry, computing them as flat.");
+        }
+    }
+
+    if let Some(vertex_attribute) = reader
+        .read_tangents()
+        .map(|v| attribute_data::AttributeData::Float32x4(v.collect()))
+    {
+        mesh_primitive.insert_attribute(
+            attribute_metadata::AttributeMetadata::ATTRIBUTE_TANGENT,
+            vertex_attribute,
+        );
+    }
+
+    mesh_primitive
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::loader::load_gltf_file;
+    use crate::loader::{load_gltf_file, stream_gltf_file, stream_gltf_primitives};
 
     #[test]
     fn test_load() {
@@ -104,5 +325,26 @@ mod tests {
 
         assert_eq!(indice_count / 3, 1256);
         println!("Total triangles (indices/3): {}", indice_count / 3);
+
+        assert!(!meshes[0].nodes.is_empty());
+        for tri_mesh in meshes[0].mesh_collection() {
+            assert_eq!(tri_mesh.uvs[&0].len(), tri_mesh.vertices.len());
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let meshes = load_gltf_file("./blend-files/Envoirment.gltf".to_string());
+        let streamed = stream_gltf_file("./blend-files/Envoirment.gltf".to_string());
+        assert_eq!(streamed.len(), meshes.len());
+
+        let primitives: Vec<_> =
+            stream_gltf_primitives("./blend-files/Envoirment.gltf".to_string()).collect();
+        assert_eq!(primitives.len(), meshes[0].mesh_primitives.len());
+
+        for (mesh_index, primitive_index, primitive) in primitives {
+            let loaded = &meshes[mesh_index].mesh_primitives[primitive_index];
+            assert_eq!(primitive.count_vertices(), loaded.count_vertices());
+        }
     }
 }
@@ -1,4 +1,5 @@
 // Values
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub enum AttributeData {
     Float32(Vec<f32>),
@@ -68,6 +69,22 @@ impl AttributeData {
         }
     }
 
+    /// Returns the values as float pairs if possible.
+    pub fn as_float2(&self) -> Option<&[[f32; 2]]> {
+        match self {
+            AttributeData::Float32x2(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Returns the values as float quadruples if possible.
+    pub fn as_float4(&self) -> Option<&[[f32; 4]]> {
+        match self {
+            AttributeData::Float32x4(values) => Some(values),
+            _ => None,
+        }
+    }
+
     /// Returns the values as float triples if possible.
     pub fn as_float3(&self) -> Option<&[[f32; 3]]> {
         match self {
@@ -1,6 +1,7 @@
 use super::AttributeData;
 
 #[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AttributeDataFormat {
     Uint8x2 = 0,
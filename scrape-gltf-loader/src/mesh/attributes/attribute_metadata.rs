@@ -2,6 +2,7 @@ use super::attribute_data_format::AttributeDataFormat;
 use crate::core::enums::AttributeType;
 
 // MeshVertexAttribute
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct AttributeMetadata {
     pub attribute_type: AttributeType,
@@ -25,6 +26,47 @@ impl AttributeMetadata {
     pub const ATTRIBUTE_TANGENT: AttributeMetadata =
         AttributeMetadata::new(AttributeType::Tangent, 4, AttributeDataFormat::Float32x4);
 
+    pub const ATTRIBUTE_COLOR: AttributeMetadata = AttributeMetadata::color(0);
+
+    // The kind of a numbered set lives above the 32 bits of its index, so the ids of
+    // different kinds never collide with each other or with the fixed attributes above
+    const COLOR_SET_ID: u64 = 1 << 32;
+    const UV_SET_ID: u64 = 2 << 32;
+
+    /// Metadata for the vertex colour set `COLOR_{set}`, always stored as linear RGBA.
+    pub const fn color(set: u32) -> Self {
+        Self::new(
+            AttributeType::Color,
+            Self::COLOR_SET_ID + set as u64,
+            AttributeDataFormat::Float32x4,
+        )
+    }
+
+    /// Metadata for the texture coordinate set `TEXCOORD_{set}`.
+    ///
+    /// Sets 0 and 1 map to [`Self::ATTRIBUTE_UV_0`] and [`Self::ATTRIBUTE_UV_1`].
+    pub const fn uv(set: u32) -> Self {
+        match set {
+            0 => Self::ATTRIBUTE_UV_0,
+            1 => Self::ATTRIBUTE_UV_1,
+            set => Self::new(
+                AttributeType::Uv,
+                Self::UV_SET_ID + set as u64,
+                AttributeDataFormat::Float32x2,
+            ),
+        }
+    }
+
+    /// Returns the set index for colour and texture coordinate attributes.
+    pub fn set(&self) -> Option<u32> {
+        match self.attribute_type {
+            AttributeType::Uv0 => Some(0),
+            AttributeType::Uv1 => Some(1),
+            AttributeType::Color | AttributeType::Uv => Some(self.id as u32),
+            _ => None,
+        }
+    }
+
     pub const fn new(attribute_type: AttributeType, id: u64, format: AttributeDataFormat) -> Self {
         Self {
             attribute_type,
@@ -33,3 +75,24 @@ impl AttributeMetadata {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::AttributeMetadata;
+
+    #[test]
+    fn set_ids_test() {
+        for set in [0, 2, 0x100, u32::MAX] {
+            assert_eq!(AttributeMetadata::color(set).set(), Some(set));
+            assert_eq!(AttributeMetadata::uv(set).set(), Some(set));
+        }
+        assert_ne!(
+            AttributeMetadata::color(0x100).id,
+            AttributeMetadata::uv(0).id
+        );
+        assert_ne!(
+            AttributeMetadata::color(0x100).id,
+            AttributeMetadata::uv(2).id
+        );
+    }
+}
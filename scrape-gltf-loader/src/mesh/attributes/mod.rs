@@ -6,6 +6,7 @@ use attribute_data::AttributeData;
 use attribute_metadata::AttributeMetadata;
 
 // MeshVertexData
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct Attribute {
     pub metadata: AttributeMetadata,
@@ -0,0 +1,202 @@
+use bevy_math::{Mat4, Vec3};
+
+use super::{attributes::attribute_metadata::AttributeMetadata, Mesh, MeshNode};
+use crate::core::enums::PrimitiveTopology;
+
+/// World-space points of a `PointList` primitive, e.g. spawn or pickup markers.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarkerSet {
+    pub mesh_index: usize,
+    pub primitive_index: usize,
+    /// The node instantiating the mesh, `None` if the mesh isn't part of any node
+    pub node: Option<MeshNode>,
+    pub points: Vec<[f32; 3]>,
+}
+
+/// World-space points of a `LineList`/`LineStrip` primitive, e.g. patrol paths, rails or zone
+/// boundaries.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LinePath {
+    pub mesh_index: usize,
+    pub primitive_index: usize,
+    /// The node instantiating the mesh, `None` if the mesh isn't part of any node
+    pub node: Option<MeshNode>,
+    pub topology: PrimitiveTopology,
+    /// Points in drawing order, with the indices already resolved
+    pub points: Vec<[f32; 3]>,
+}
+
+impl LinePath {
+    /// Returns the individual segments, pairing up points for line lists and
+    /// connecting consecutive points for line strips.
+    pub fn segments(&self) -> Vec<[[f32; 3]; 2]> {
+        match self.topology {
+            PrimitiveTopology::LineList => self
+                .points
+                .chunks_exact(2)
+                .map(|segment| [segment[0], segment[1]])
+                .collect(),
+            _ => self
+                .points
+                .windows(2)
+                .map(|segment| [segment[0], segment[1]])
+                .collect(),
+        }
+    }
+}
+
+impl Mesh {
+    /// Extracts every `PointList` primitive once per node instantiating the mesh.
+    pub fn marker_sets(&self) -> Vec<MarkerSet> {
+        self.instanced_points(|topology| matches!(topology, PrimitiveTopology::PointList))
+            .into_iter()
+            .map(|(primitive_index, node, points)| MarkerSet {
+                mesh_index: self.index,
+                primitive_index,
+                node,
+                points,
+            })
+            .collect()
+    }
+
+    /// Extracts every `LineList`/`LineStrip` primitive once per node instantiating the mesh.
+    pub fn line_paths(&self) -> Vec<LinePath> {
+        self.instanced_points(|topology| {
+            matches!(
+                topology,
+                PrimitiveTopology::LineList | PrimitiveTopology::LineStrip
+            )
+        })
+        .into_iter()
+        .map(|(primitive_index, node, points)| LinePath {
+            mesh_index: self.index,
+            primitive_index,
+            topology: self.mesh_primitives[primitive_index].topology.clone(),
+            node,
+            points,
+        })
+        .collect()
+    }
+
+    /// Resolves the indexed positions of the matching primitives and moves them into world space.
+    fn instanced_points(
+        &self,
+        topology_filter: impl Fn(&PrimitiveTopology) -> bool,
+    ) -> Vec<(usize, Option<MeshNode>, Vec<[f32; 3]>)> {
+        let nodes: Vec<Option<&MeshNode>> = match self.nodes.is_empty() {
+            true => vec![None],
+            false => self.nodes.iter().map(Some).collect(),
+        };
+
+        let mut instances = Vec::new();
+        for (primitive_index, primitive) in self.mesh_primitives.iter().enumerate() {
+            if !topology_filter(&primitive.topology) {
+                continue;
+            }
+
+            let Some(positions) = primitive
+                .attribute(AttributeMetadata::ATTRIBUTE_POSITION)
+                .and_then(|data| data.as_float3())
+            else {
+                continue;
+            };
+
+            let points: Vec<[f32; 3]> = match &primitive.indices {
+                Some(indices) => indices
+                    .iter()
+                    .filter_map(|index| positions.get(index).copied())
+                    .collect(),
+                None => positions.to_vec(),
+            };
+
+            for node in nodes.iter() {
+                let transform = node
+                    .map(|node| Mat4::from_cols_array_2d(&node.transform))
+                    .unwrap_or(Mat4::IDENTITY);
+                let world_points = points
+                    .iter()
+                    .map(|point| transform.transform_point3(Vec3::from(*point)).into())
+                    .collect();
+                instances.push((primitive_index, node.cloned(), world_points));
+            }
+        }
+
+        instances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::enums::PrimitiveTopology;
+    use crate::indices::IndexVec;
+    use crate::mesh::{
+        attributes::{attribute_data::AttributeData, attribute_metadata::AttributeMetadata},
+        mesh_primitive::MeshPrimitive,
+        Mesh, MeshNode,
+    };
+
+    fn primitive(topology: PrimitiveTopology, indices: Option<IndexVec>) -> MeshPrimitive {
+        let mut primitive = MeshPrimitive::new(topology);
+        primitive.insert_attribute(
+            AttributeMetadata::ATTRIBUTE_POSITION,
+            AttributeData::Float32x3(vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0]]),
+        );
+        primitive.set_indices(indices);
+        primitive
+    }
+
+    #[test]
+    fn test_markers() {
+        let translation = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 2.0, 0.0, 1.0],
+        ];
+        let mesh = Mesh {
+            index: 0,
+            name: None,
+            nodes: vec![MeshNode {
+                index: 0,
+                name: Some("patrol".to_string()),
+                transform: translation,
+            }],
+            mesh_primitives: vec![
+                primitive(PrimitiveTopology::PointList, None),
+                primitive(
+                    PrimitiveTopology::LineStrip,
+                    Some(IndexVec::U16(vec![2, 1, 0])),
+                ),
+                primitive(
+                    PrimitiveTopology::TriangleList,
+                    Some(IndexVec::U16(vec![0, 1, 2])),
+                ),
+            ],
+        };
+
+        let marker_sets = mesh.marker_sets();
+        assert_eq!(marker_sets.len(), 1);
+        assert_eq!(marker_sets[0].points[1], [1.0, 2.0, 0.0]);
+
+        let paths = mesh.line_paths();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].primitive_index, 1);
+        assert_eq!(
+            paths[0].node.as_ref().unwrap().name.as_deref(),
+            Some("patrol")
+        );
+        assert_eq!(
+            paths[0].segments(),
+            vec![
+                [[1.0, 2.0, 1.0], [1.0, 2.0, 0.0]],
+                [[1.0, 2.0, 0.0], [0.0, 2.0, 0.0]]
+            ]
+        );
+
+        let tri_meshes = mesh.mesh_collection();
+        assert_eq!(tri_meshes.len(), 1);
+        assert_eq!(tri_meshes[0].primitive_index, 2);
+    }
+}
@@ -6,11 +6,15 @@ use crate::mesh::attributes::{
     attribute_data::AttributeData, attribute_metadata::AttributeMetadata, Attribute,
 };
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct MeshPrimitive {
     pub topology: PrimitiveTopology,
     pub attributes: BTreeMap<u64, Attribute>,
     pub indices: Option<IndexVec>,
+    /// Index of the material in the glTF document, `None` for the default material
+    pub material_index: Option<usize>,
+    pub material_name: Option<String>,
 }
 
 impl MeshPrimitive {
@@ -19,6 +23,8 @@ impl MeshPrimitive {
             topology,
             attributes: BTreeMap::new(),
             indices: None,
+            material_index: None,
+            material_name: None,
         }
     }
 
@@ -50,6 +56,20 @@ impl MeshPrimitive {
             .map(|data| &data.data)
     }
 
+    /// Retrieves the RGBA values of the vertex colour set `COLOR_{set}`.
+    #[inline]
+    pub fn colors(&self, set: u32) -> Option<&[[f32; 4]]> {
+        self.attribute(AttributeMetadata::color(set))
+            .and_then(AttributeData::as_float4)
+    }
+
+    /// Retrieves the values of the texture coordinate set `TEXCOORD_{set}`.
+    #[inline]
+    pub fn uvs(&self, set: u32) -> Option<&[[f32; 2]]> {
+        self.attribute(AttributeMetadata::uv(set))
+            .and_then(AttributeData::as_float2)
+    }
+
     /// Counts all vertices of the mesh.
     ///
     /// If the attributes have different vertex counts, the smallest is returned.
@@ -1,26 +1,108 @@
 pub mod attributes;
+pub mod markers;
 pub mod mesh_primitive;
 
+use std::collections::BTreeMap;
+
 use mesh_primitive::MeshPrimitive;
 
-use self::attributes::attribute_metadata::AttributeMetadata;
+use self::attributes::{attribute_data::AttributeData, attribute_metadata::AttributeMetadata};
+use crate::core::enums::{AttributeType, PrimitiveTopology};
 
 // Add 'Copy' trait as derived
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct Mesh {
+    /// Index of the mesh in the glTF document
+    pub index: usize,
+    pub name: Option<String>,
+    /// Scene nodes which instantiate this mesh
+    pub nodes: Vec<MeshNode>,
     pub mesh_primitives: Vec<MeshPrimitive>,
 }
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshNode {
+    /// Index of the node in the glTF document
+    pub index: usize,
+    pub name: Option<String>,
+    /// Column-major world transform of the node
+    pub transform: [[f32; 4]; 4],
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SubTriMesh {
+    /// Index of the primitive this trimesh was built from in [`Mesh::mesh_primitives`]
+    pub primitive_index: usize,
     pub vertices: Vec<[f32; 3]>,
     pub indices: Vec<[u32; 3]>,
+    /// Vertex colour sets (`COLOR_n`), keyed by the set index
+    pub colors: BTreeMap<u32, Vec<[f32; 4]>>,
+    /// Texture coordinate sets (`TEXCOORD_n`), keyed by the set index
+    pub uvs: BTreeMap<u32, Vec<[f32; 2]>>,
+}
+
+/// Per-vertex data channels interpolated at a point on a triangle.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VertexSample {
+    pub colors: BTreeMap<u32, [f32; 4]>,
+    pub uvs: BTreeMap<u32, [f32; 2]>,
+}
+
+impl SubTriMesh {
+    /// Interpolates the colour and UV channels of `triangle` with the given barycentric coordinates.
+    ///
+    /// Returns `None` if the triangle does not exist.
+    pub fn interpolate(&self, triangle: usize, barycentric: [f32; 3]) -> Option<VertexSample> {
+        let corners = self.indices.get(triangle)?.map(|index| index as usize);
+
+        Some(VertexSample {
+            colors: interpolate_channels(&self.colors, corners, barycentric),
+            uvs: interpolate_channels(&self.uvs, corners, barycentric),
+        })
+    }
+}
+
+fn interpolate_channels<const N: usize>(
+    channels: &BTreeMap<u32, Vec<[f32; N]>>,
+    corners: [usize; 3],
+    barycentric: [f32; 3],
+) -> BTreeMap<u32, [f32; N]> {
+    channels
+        .iter()
+        .filter_map(|(set, values)| {
+            let mut result = [0.0; N];
+            for (corner, weight) in corners.iter().zip(barycentric) {
+                let value = values.get(*corner)?;
+                for (component, value) in result.iter_mut().zip(value) {
+                    *component += value * weight;
+                }
+            }
+            Some((*set, result))
+        })
+        .collect()
 }
 
 impl Mesh {
+    /// Builds a trimesh for every triangle list primitive, point and line primitives are
+    /// available through [`Mesh::marker_sets`] and [`Mesh::line_paths`] instead.
     pub fn mesh_collection(&self) -> Vec<SubTriMesh> {
         let mut sub_tri_meshes = Vec::new();
-        for (idx, _primitive) in self.mesh_primitives.iter().enumerate() {
-            sub_tri_meshes.push(SubTriMesh { vertices: self.get_vertices(idx), indices: self.get_indices(idx) })
+        for (idx, primitive) in self.mesh_primitives.iter().enumerate() {
+            if !matches!(primitive.topology, PrimitiveTopology::TriangleList) {
+                continue;
+            }
+
+            sub_tri_meshes.push(SubTriMesh {
+                primitive_index: idx,
+                vertices: self.get_vertices(idx),
+                indices: self.get_indices(idx),
+                colors: self.get_channels(idx, AttributeType::Color, |data| data.as_float4()),
+                uvs: self.get_channels(idx, AttributeType::Uv, |data| data.as_float2()),
+            })
         }
 
         sub_tri_meshes
@@ -44,6 +126,36 @@ impl Mesh {
         return data;
     }
 
+    /// Collects every colour or UV set of the primitive, keyed by the set index.
+    ///
+    /// Passing [`AttributeType::Uv`] also includes the `Uv0`/`Uv1` sets.
+    fn get_channels<T: Copy>(
+        &self,
+        index: usize,
+        attribute_type: AttributeType,
+        values: impl Fn(&AttributeData) -> Option<&[T]>,
+    ) -> BTreeMap<u32, Vec<T>> {
+        let Some(primitive) = self.mesh_primitives.get(index) else {
+            return BTreeMap::new();
+        };
+
+        primitive
+            .attributes
+            .values()
+            .filter(|attribute| match attribute_type {
+                AttributeType::Uv => matches!(
+                    attribute.metadata.attribute_type,
+                    AttributeType::Uv0 | AttributeType::Uv1 | AttributeType::Uv
+                ),
+                _ => attribute.metadata.attribute_type == attribute_type,
+            })
+            .filter_map(|attribute| {
+                let set = attribute.metadata.set()?;
+                values(&attribute.data).map(|data| (set, data.to_vec()))
+            })
+            .collect()
+    }
+
     pub fn get_indices(&self, index: usize) -> Vec<[u32; 3]> {
         let primitives = &self.mesh_primitives;
         let mut indices = Vec::new();
@@ -65,3 +177,34 @@ impl Mesh {
         return indices;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::SubTriMesh;
+
+    #[test]
+    fn test_interpolate() {
+        let tri_mesh = SubTriMesh {
+            primitive_index: 0,
+            vertices: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]],
+            indices: vec![[0, 1, 2]],
+            colors: BTreeMap::from([(
+                0,
+                vec![
+                    [1.0, 0.0, 0.0, 1.0],
+                    [0.0, 1.0, 0.0, 1.0],
+                    [0.0, 0.0, 1.0, 1.0],
+                ],
+            )]),
+            uvs: BTreeMap::from([(2, vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])]),
+        };
+
+        let sample = tri_mesh.interpolate(0, [0.5, 0.25, 0.25]).unwrap();
+        assert_eq!(sample.colors[&0], [0.5, 0.25, 0.25, 1.0]);
+        assert_eq!(sample.uvs[&2], [0.25, 0.25]);
+
+        assert!(tri_mesh.interpolate(1, [1.0, 0.0, 0.0]).is_none());
+    }
+}
@@ -1,7 +1,7 @@
 use gltf::{
     accessor::{DataType, Dimensions},
     buffer::Data,
-    mesh::util::ReadTexCoords,
+    mesh::util::{ReadColors, ReadTexCoords},
 };
 
 pub mod buffer_accessor;
@@ -128,6 +128,28 @@ impl<'a> VertexAttributeIter<'a> {
         }
     }
 
+    /// Materializes RGBA values, converting all colour formats to Float32x4
+    pub fn into_rgba_values(self) -> Result<AttributeData, AccessFailed> {
+        match self {
+            VertexAttributeIter::U8x3(it, Normalization(true)) => Ok(AttributeData::Float32x4(
+                ReadColors::RgbU8(it).into_rgba_f32().collect(),
+            )),
+            VertexAttributeIter::U16x3(it, Normalization(true)) => Ok(AttributeData::Float32x4(
+                ReadColors::RgbU16(it).into_rgba_f32().collect(),
+            )),
+            VertexAttributeIter::F32x3(it) => Ok(AttributeData::Float32x4(
+                ReadColors::RgbF32(it).into_rgba_f32().collect(),
+            )),
+            VertexAttributeIter::U8x4(it, Normalization(true)) => Ok(AttributeData::Float32x4(
+                ReadColors::RgbaU8(it).into_rgba_f32().collect(),
+            )),
+            VertexAttributeIter::U16x4(it, Normalization(true)) => Ok(AttributeData::Float32x4(
+                ReadColors::RgbaU16(it).into_rgba_f32().collect(),
+            )),
+            s => s.into_any_values(),
+        }
+    }
+
     /// Materializes texture coordinate values, converting compatible formats to Float32x2
     pub fn into_tex_coord_values(self) -> Result<AttributeData, AccessFailed> {
         match self {
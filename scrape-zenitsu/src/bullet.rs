@@ -1,61 +1,69 @@
 use std::string;
 
-use scrape_collision::collider::{
-    CharacterCollision, ColliderHandle, GameCollider, RigidBodyHandle,
-};
+use scrape_collision::collider::{ColliderHandle, GameCollider, MovementRequest, ProjectileSweep};
+use scrape_collision::entity::{EntityDescriptor, EntityId, EntityKind};
+use scrape_collision::errors::CollisionError;
+use scrape_collision::layers::CollisionLayers;
+use scrape_collision::queries::QueryHit;
+use scrape_collision::shape::EntityShape;
 use uuid::Uuid;
 
 use crate::input_messages::Direction;
 use crate::{geometry::*, output_messages};
 
+/// Distance a bullet flies without hitting anything before it expires
+const MAX_RANGE: f32 = 200.0;
+
 #[derive(Clone)]
 pub struct BulletInfo {
     pub id: uuid::Uuid,
     pub position: Position,
     velocity: Velocity,
     fired_by: ColliderHandle,
-    body_handle: RigidBodyHandle,
-    collider_handle: ColliderHandle,
+    entity_id: EntityId,
+    /// Distance left until the bullet expires
+    remaining_range: f32,
 }
 
 impl BulletInfo {
-    pub fn update_position_in(
-        &mut self,
-        collider: &mut GameCollider,
-        delta: u128,
-    ) -> (
-        Vec<CharacterCollision>,
-        output_messages::UpdateBulletPosition,
-    ) {
+    pub fn movement_request(&self, dt: f32) -> MovementRequest {
         let mut desired = vec![];
-        desired.push((delta as f32 / 1000.0) * self.velocity.velocity_x);
-        desired.push((delta as f32 / 1000.0) * self.velocity.velocity_y);
-        desired.push((delta as f32 / 1000.0) * self.velocity.velocity_z);
+        desired.push(dt * self.velocity.velocity_x);
+        desired.push(dt * self.velocity.velocity_y);
+        desired.push(dt * self.velocity.velocity_z);
 
         let mut exclude_filter = Vec::new();
         exclude_filter.push(self.fired_by);
         // println!("Fired By: {:#?}", self.fired_by);
 
-        let movement = collider.calculate_movement(self.body_handle, exclude_filter, desired);
-        if !movement.collisions.is_empty() {
-            // println!("Movement collisions: {:?}", movement.collisions);
+        MovementRequest {
+            entity_id: self.entity_id,
+            exclude_colliders: exclude_filter,
+            desired,
         }
+    }
 
-        let calculated_position = movement.next_position;
-        let bullet_body = collider.get_mut_entity(self.body_handle);
+    pub fn apply_movement(
+        &mut self,
+        collider: &mut GameCollider,
+        sweep: ProjectileSweep,
+    ) -> Result<(Option<QueryHit>, output_messages::UpdateBulletPosition), CollisionError> {
+        let calculated_position = sweep.next_position;
+        self.remaining_range -= calculated_position.norm();
+        let bullet_body = collider.get_mut_entity(self.entity_id)?;
         bullet_body.set_next_kinematic_translation(bullet_body.translation() + calculated_position);
         let next_position = bullet_body.next_position().translation;
 
-        (
-            movement.collisions,
+        Ok((
+            sweep.hit,
             output_messages::UpdateBulletPosition {
                 id: self.id.to_string(),
                 x: next_position.x,
                 y: next_position.y,
                 z: next_position.z,
-                destroy: false,
+                destroy: self.remaining_range <= 0.0,
             },
-        )
+        ))
     }
 }
 
@@ -72,24 +80,34 @@ impl BasicBullet {
         position: Position,
         direction: Direction,
         fired_by: ColliderHandle,
-    ) -> Self {
-        let default_speed = 0.1;
-
-        let (body_handle, collider_handle) =
-            collider.load_entity(vec![position.x, position.y, position.z]);
-
-        Self {
+    ) -> Result<Self, CollisionError> {
+        // Units per second, bullets are swept so they can't skip through walls at this speed
+        let default_speed = 80.0;
+
+        let id = uuid::Uuid::new_v4();
+        let entity_id = collider.load_entity_with(
+            EntityDescriptor::new(
+                vec![position.x, position.y, position.z],
+                EntityShape::Ball { radius: 0.05 },
+            )
+            .layers(CollisionLayers::projectile(None))
+            // Half of the uuid is plenty to tell the bullets apart
+            .tag(EntityKind::Projectile, id.as_u64_pair().0),
+        )?;
+        collider.set_entity_gravity_scale(entity_id, 0.0)?;
+
+        Ok(Self {
             bullet_info: BulletInfo {
-                id: uuid::Uuid::new_v4(),
+                id,
                 position,
                 velocity: Velocity::new(direction, default_speed),
                 fired_by,
-                body_handle,
-                collider_handle,
+                entity_id,
+                remaining_range: MAX_RANGE,
             },
             speed: default_speed,
             damage: 20,
-        }
+        })
     }
 }
 
@@ -99,43 +117,45 @@ pub enum Bullet {
 }
 
 pub trait TickUpdate {
-    fn update_position(
+    fn movement_request(&self, dt: f32) -> MovementRequest;
+
+    fn apply_movement(
         &mut self,
         collider: &mut GameCollider,
-        delta: u128,
-    ) -> (
-        Vec<CharacterCollision>,
-        output_messages::UpdateBulletPosition,
-    );
-
-    fn get_body_handle(&self) -> RigidBodyHandle;
-    fn get_collider_handle(&self) -> ColliderHandle;
+        sweep: ProjectileSweep,
+    ) -> Result<(Option<QueryHit>, output_messages::UpdateBulletPosition), CollisionError>;
+
+    fn get_entity_id(&self) -> EntityId;
+    fn get_damage(&self) -> i16;
     fn in_vector(&self, bullets: &Vec<String>) -> bool;
 }
 
 impl TickUpdate for Bullet {
-    fn update_position(
+    fn movement_request(&self, dt: f32) -> MovementRequest {
+        match self {
+            Self::Basic { bullet } => bullet.bullet_info.movement_request(dt),
+        }
+    }
+
+    fn apply_movement(
         &mut self,
         collider: &mut GameCollider,
-        delta: u128,
-    ) -> (
-        Vec<CharacterCollision>,
-        output_messages::UpdateBulletPosition,
-    ) {
+        sweep: ProjectileSweep,
+    ) -> Result<(Option<QueryHit>, output_messages::UpdateBulletPosition), CollisionError> {
         match self {
-            Self::Basic { bullet } => bullet.bullet_info.update_position_in(collider, delta),
+            Self::Basic { bullet } => bullet.bullet_info.apply_movement(collider, sweep),
         }
     }
 
-    fn get_body_handle(&self) -> RigidBodyHandle {
+    fn get_entity_id(&self) -> EntityId {
         match self {
-            Bullet::Basic { bullet } => bullet.bullet_info.body_handle,
+            Bullet::Basic { bullet } => bullet.bullet_info.entity_id,
         }
     }
 
-    fn get_collider_handle(&self) -> ColliderHandle {
+    fn get_damage(&self) -> i16 {
         match self {
-            Bullet::Basic { bullet } => bullet.bullet_info.collider_handle,
+            Bullet::Basic { bullet } => bullet.damage,
         }
     }
 
@@ -1,12 +1,18 @@
 use std::borrow::BorrowMut;
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::u128;
-
-use scrape_collision::collider::{ColliderHandle, TOIStatus};
+use std::time::Duration;
+
+use scrape_collision::collider::{GameCollider, MovementRequest, Vector};
+use scrape_collision::entity::{EntityDescriptor, EntityKind};
+use scrape_collision::events::EventTarget;
+use scrape_collision::hitbox::Hitbox;
+use scrape_collision::layers::CollisionLayers;
+use scrape_collision::map::MapGeometry;
+use scrape_collision::orientation;
+use scrape_collision::queries::QueryHit;
 use scrape_collision::rapier::IntoRapier;
-use scrape_collision::{
-    collider::CharacterCollision, collider::GameCollider, helpers::IntoDirection,
-};
+use scrape_collision::shape::EntityShape;
 
 use crate::bullet::BasicBullet;
 use crate::game_state::GameState;
@@ -23,46 +29,69 @@ use crate::bullet::{Bullet, TickUpdate};
 use output_messages::update_game_event::UpdateEvent;
 
 pub struct GameInfo {
-    players: Vec<Player>,
+    /// Keyed by the tag id stored on each player's collider
+    players: HashMap<u64, Player>,
     bullets: Vec<Bullet>,
     state: GameState,
     collider: GameCollider,
+    next_player_tag: u64,
 
     // DEBUG
     counter: i32,
 }
 
 impl GameInfo {
+    /// A match on a map which other matches of the process can share, including its trigger volumes.
+    pub fn new(map: &MapGeometry) -> Self {
+        GameInfo {
+            players: HashMap::new(),
+            bullets: Vec::new(),
+            state: GameState {},
+            collider: GameCollider::with_map(map),
+            next_player_tag: 0,
+            counter: 0,
+        }
+    }
+
     pub fn get_addresses(&self) -> Vec<SocketAddr> {
         self.players
-            .iter()
+            .values()
             .map(|player| player.server_info.addr)
             .collect()
     }
 
     pub fn add_player(&mut self, data: PlayerJoined, addr: SocketAddr) -> Option<UpdateEvent> {
-        let player_exists = self
-            .players
-            .iter()
-            .any(|player| player.server_info.addr == addr);
-        if !player_exists {
+        if self.find_player_by_address(addr).is_none() {
             let mut rng = rand::thread_rng();
             let position = Position {
                 x: rng.gen_range(2.0..10.0),
                 y: 5.0,
                 z: rng.gen_range(2.0..10.0),
             };
-            let (body_handle, collider_handle) = self
-                .collider
-                .load_entity(vec![position.x, position.y, position.z]);
+            let entity_id = match self.collider.load_entity_with(
+                EntityDescriptor::new(
+                    vec![position.x, position.y, position.z],
+                    EntityShape::default(),
+                )
+                .layers(CollisionLayers::player(None))
+                .tag(EntityKind::Player, self.next_player_tag)
+                .hitboxes(Hitbox::humanoid()),
+            ) {
+                Ok(entity_id) => entity_id,
+                Err(err) => {
+                    eprintln!("Couldn't load player {}: {}", data.id, err);
+                    return None;
+                }
+            };
             let player = Player::new(
                 data.id.clone(),
                 data.username.clone(),
                 addr,
-                body_handle,
-                collider_handle,
+                entity_id,
+                self.next_player_tag,
             );
-            self.players.push(player);
+            self.players.insert(self.next_player_tag, player);
+            self.next_player_tag += 1;
             return Some(UpdateEvent::AddedPlayer(output_messages::AddedPlayer {
                 id: data.id,
                 username: data.username,
@@ -73,13 +102,13 @@ impl GameInfo {
     }
 
     pub fn remove_player(&mut self, _data: PlayerLeft, addr: SocketAddr) -> Option<UpdateEvent> {
-        let player_exists = self
-            .players
-            .iter()
-            .position(|player| player.server_info.addr == addr);
-        if let Some(pos) = player_exists {
-            let player = self.players.swap_remove(pos);
-            self.collider.unload_entity(player.body_handle);
+        let tag_id = self
+            .find_player_by_address(addr)
+            .map(|player| player.tag_id);
+        if let Some(player) = tag_id.and_then(|tag_id| self.players.remove(&tag_id)) {
+            if let Err(err) = self.collider.unload_entity(player.entity_id) {
+                eprintln!("Couldn't unload player {}: {}", player.id, err);
+            }
             return Some(UpdateEvent::RemovedPlayer(output_messages::RemovedPlayer {
                 id: player.id,
             }));
@@ -89,33 +118,71 @@ impl GameInfo {
     }
 
     pub fn move_player(&mut self, data: Move, addr: SocketAddr) -> Option<UpdateEvent> {
-        let player_exists = self
+        let player = self
             .players
-            .iter()
-            .position(|player| player.server_info.addr == addr);
-        if let Some(pos) = player_exists {
-            let player = self.players.get_mut(pos).unwrap();
-            let desired = vec![data.distance_x, data.distance_y, data.distance_z];
-            let calculated_position = self
-                .collider
-                .calculate_movement(player.body_handle, Vec::new(), desired)
-                .next_position;
-
-            let player_body = self.collider.get_mut_entity(player.body_handle);
+            .values_mut()
+            .find(|player| player.server_info.addr == addr);
+        if let Some(player) = player {
+            player.pending_move[0] += data.distance_x;
+            player.pending_move[1] += data.distance_y;
+            player.pending_move[2] += data.distance_z;
+        }
+
+        None
+    }
+
+    pub fn jump_player(&mut self, _data: Jump, addr: SocketAddr) -> Option<UpdateEvent> {
+        let player = self.find_player_by_address(addr)?.clone();
+        if let Err(err) = self.collider.jump(player.entity_id, JUMP_SPEED) {
+            eprintln!("Couldn't jump with player {}: {}", player.id, err);
+        }
+
+        None
+    }
+
+    /// Resolves the moves every player sent since the last step in one batch, players who didn't
+    /// send any still fall (or finish their jump).
+    fn update_player_movements(&mut self) -> Vec<Option<UpdateEvent>> {
+        let mut players: Vec<&mut Player> = self.players.values_mut().collect();
+        let requests = players
+            .iter_mut()
+            .map(|player| MovementRequest {
+                entity_id: player.entity_id,
+                exclude_colliders: Vec::new(),
+                desired: std::mem::take(&mut player.pending_move).to_vec(),
+            })
+            .collect();
+        let movements = self.collider.calculate_movements(requests);
+
+        let mut events = Vec::new();
+        for (player, movement) in players.into_iter().zip(movements) {
+            let calculated_position = match movement {
+                Ok(movement) => movement.next_position,
+                Err(err) => {
+                    eprintln!("Couldn't move player {}: {}", player.id, err);
+                    continue;
+                }
+            };
+            if calculated_position == Vector::zeros() {
+                continue;
+            }
+            let Ok(player_body) = self.collider.get_mut_entity(player.entity_id) else {
+                continue;
+            };
             player_body
                 .set_next_kinematic_translation(player_body.translation() + calculated_position);
             let next_position = player_body.next_position().translation;
-            return Some(UpdateEvent::ChangedPlayerPosition(
+            events.push(Some(UpdateEvent::ChangedPlayerPosition(
                 output_messages::ChangedPlayerPosition {
                     id: player.id.clone(),
                     x: next_position.x,
                     y: next_position.y,
                     z: next_position.z,
                 },
-            ));
+            )));
         }
 
-        None
+        events
     }
 
     pub fn update_camera_rotation(
@@ -133,28 +200,24 @@ impl GameInfo {
 
         println!("Updated rotation: {:?} - w: {}", direction, w);
 
-        self.collider.update_entity_rotation(
-            player.unwrap().body_handle,
+        let result = self.collider.update_entity_rotation(
+            player.unwrap().entity_id,
             direction.direction_x,
             direction.direction_y,
             direction.direction_z,
             w,
         );
+        if let Err(err) = result {
+            eprintln!("Couldn't update rotation: {}", err);
+        }
 
         None
     }
 
     fn find_player_by_address(&self, addr: SocketAddr) -> Option<&Player> {
-        let player_exists = self
-            .players
-            .iter()
-            .position(|player| player.server_info.addr == addr);
-        if let Some(pos) = player_exists {
-            let player = self.players.get(pos).unwrap();
-            return Some(player);
-        }
-
-        None
+        self.players
+            .values()
+            .find(|player| player.server_info.addr == addr)
     }
 
     pub fn shoot_bullet(
@@ -167,12 +230,17 @@ impl GameInfo {
             return None;
         }
 
-        let player_body_handle = player.unwrap().body_handle;
-        let player_collider_handle = player.unwrap().collider_handle;
+        let player_entity_id = player.unwrap().entity_id;
 
-        let player_body = self.collider.get_entity(player_body_handle);
+        let player_body = match self.collider.get_entity(player_entity_id) {
+            Ok(player_body) => player_body,
+            Err(err) => {
+                eprintln!("Couldn't shoot bullet: {}", err);
+                return None;
+            }
+        };
         let player_position = player_body.translation();
-        let player_rotation = player_body.rotation().into_inner().into_direction();
+        let player_rotation = orientation::forward(player_body.rotation());
         println!(
             "Direction: x: {} - y: {} - z: {}",
             player_rotation.x, player_rotation.y, player_rotation.z
@@ -182,8 +250,7 @@ impl GameInfo {
             y: player_position.y,
             z: player_position.z,
         };
-        // TODO: Fix
-        let basic_bullet = BasicBullet::new(
+        let basic_bullet = match BasicBullet::new(
             &mut self.collider,
             position,
             Direction {
@@ -191,8 +258,14 @@ impl GameInfo {
                 direction_y: player_rotation.y,
                 direction_z: player_rotation.z,
             },
-            player_collider_handle,
-        );
+            player_entity_id.collider(),
+        ) {
+            Ok(basic_bullet) => basic_bullet,
+            Err(err) => {
+                eprintln!("Couldn't load bullet: {}", err);
+                return None;
+            }
+        };
 
         let response = Some(UpdateEvent::CreateBullet(
             output_messages::CreateBullet::new(&basic_bullet.bullet_info),
@@ -203,15 +276,29 @@ impl GameInfo {
         response
     }
 
-    pub fn update_bullets(&mut self, delta: u128) -> Option<UpdateEvent> {
+    pub fn update_bullets(&mut self) -> Option<UpdateEvent> {
         let mut bullet_changes: Vec<output_messages::UpdateBulletPosition> = Vec::new();
         if self.players.is_empty() {
             return None;
         }
-        for bullet in self.bullets.iter_mut() {
-            let (collisions, mut updates) = bullet.update_position(&mut self.collider, delta);
-            let collided = Self::bullet_collided(&self.players, bullet, collisions);
-            if collided {
+        let dt = self.collider.fixed_dt();
+        let requests = self
+            .bullets
+            .iter()
+            .map(|bullet| bullet.movement_request(dt))
+            .collect();
+        let sweeps = self.collider.sweep_projectiles(requests);
+        for (bullet, sweep) in self.bullets.iter_mut().zip(sweeps) {
+            let (hit, mut updates) =
+                match sweep.and_then(|sweep| bullet.apply_movement(&mut self.collider, sweep)) {
+                    Ok(update) => update,
+                    Err(err) => {
+                        eprintln!("Couldn't update bullet: {}", err);
+                        continue;
+                    }
+                };
+            if let Some(hit) = hit {
+                Self::bullet_collided(&self.collider, &mut self.players, bullet, hit);
                 updates.destroy = true;
             }
             bullet_changes.push(updates.clone());
@@ -222,12 +309,16 @@ impl GameInfo {
             .filter(|bul| bul.destroy)
             .map(|bul| bul.id.clone())
             .collect();
-        self.bullets = self
-            .bullets
-            .clone()
+        let (destroyed, bullets): (Vec<Bullet>, Vec<Bullet>) = std::mem::take(&mut self.bullets)
             .into_iter()
-            .filter(|bul| !bul.in_vector(&to_destroy))
-            .collect();
+            .partition(|bul| bul.in_vector(&to_destroy));
+        self.bullets = bullets;
+        // Whether they hit something or expired, their bodies would stay in the world otherwise
+        for bullet in destroyed {
+            if let Err(err) = self.collider.unload_entity(bullet.get_entity_id()) {
+                eprintln!("Couldn't unload bullet: {}", err);
+            }
+        }
 
         Some(UpdateEvent::UpdateAllBullets(
             output_messages::UpdateAllBullets {
@@ -236,31 +327,48 @@ impl GameInfo {
         ))
     }
 
+    /// Damages the player the bullet hit, anything else (e.g. a wall) only stops it.
     pub fn bullet_collided(
-        players: &Vec<Player>,
+        collider: &GameCollider,
+        players: &mut HashMap<u64, Player>,
         bullet: &Bullet,
-        collisions: Vec<CharacterCollision>,
-    ) -> bool {
-        let player_colliders: Vec<ColliderHandle> = players
-            .iter()
-            .map(|player| player.collider_handle)
-            .collect();
-        let useful_collisions: Vec<&CharacterCollision> = collisions
-            .iter()
-            .filter(|col| player_colliders.contains(&col.handle))
-            .collect();
-        if !useful_collisions.is_empty() {
-            println!("Bullet collider: {:#?}", bullet.get_collider_handle());
-            return true;
+        hit: QueryHit,
+    ) {
+        let tag = collider.collider_tag(hit.target.collider());
+        let player = tag
+            .filter(|tag| tag.kind == EntityKind::Player)
+            .and_then(|tag| players.get_mut(&tag.id));
+        if let Some(player) = player {
+            player.health -= i32::from(bullet.get_damage());
+            let part = match hit.target {
+                EventTarget::Hitbox { part, .. } => format!("{:?}", part),
+                _ => "body".to_string(),
+            };
+            println!(
+                "Bullet collider {:#?} hit player {} in the {}, {} health left",
+                bullet.get_entity_id().collider(),
+                player.id,
+                part,
+                player.health
+            );
         }
+    }
 
-        false
+    /// Runs a game tick for every fixed step that is due after `elapsed` wall-clock time.
+    pub fn advance(&mut self, elapsed: Duration) -> Vec<Option<UpdateEvent>> {
+        let steps = self.collider.advance(elapsed);
+        let mut events = Vec::new();
+        for _ in 0..steps {
+            events.extend(self.game_tick());
+        }
+        events
     }
 
-    pub fn game_tick(&mut self, delta: u128) -> Vec<Option<UpdateEvent>> {
+    pub fn game_tick(&mut self) -> Vec<Option<UpdateEvent>> {
         let mut events = Vec::new();
-        let bullet_update = self.update_bullets(delta);
+        let bullet_update = self.update_bullets();
         events.push(bullet_update);
+        events.extend(self.update_player_movements());
         self.collider.run_step();
         events
     }
@@ -268,12 +376,6 @@ impl GameInfo {
 
 impl Default for GameInfo {
     fn default() -> Self {
-        GameInfo {
-            players: Vec::new(),
-            bullets: Vec::new(),
-            state: GameState {},
-            collider: GameCollider::new("./data/environment.gltf".to_string()),
-            counter: 0,
-        }
+        Self::new(&MapGeometry::load("./data/environment.gltf".to_string()))
     }
 }
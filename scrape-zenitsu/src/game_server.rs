@@ -66,7 +66,7 @@ impl GameServer {
             let delta = start - last_loop;
 
             let mut events = self.process_all_user_events().await?;
-            events.extend(self.game.game_tick(delta));
+            events.extend(self.game.advance(Duration::from_millis(delta as u64)));
             let _ = self.update_clients(&mut events).await;
 
             last_loop = start;
@@ -102,6 +102,7 @@ impl GameServer {
             Event::Left(payload) => self.game.remove_player(payload, addr),
             Event::Shoot(payload) => self.game.shoot_bullet(payload, addr),
             Event::UpdateCamera(payload) => self.game.update_camera_rotation(payload, addr),
+            Event::Jump(payload) => self.game.jump_player(payload, addr),
         }
     }
 
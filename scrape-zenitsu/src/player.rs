@@ -2,7 +2,10 @@ use std::net::SocketAddr;
 
 use crate::networking::*;
 
-use scrape_collision::collider::{ColliderHandle, RigidBodyHandle};
+use scrape_collision::entity::EntityId;
+
+/// Upward speed a jump starts with, in units per second
+pub const JUMP_SPEED: f32 = 5.0;
 
 #[derive(Clone)]
 pub struct Player {
@@ -10,19 +13,29 @@ pub struct Player {
     pub username: String,
     pub server_info: PlayerServerInfo,
     pub health: i32,
-    pub body_handle: RigidBodyHandle,
-    pub collider_handle: ColliderHandle
+    pub entity_id: EntityId,
+    /// Game id stored on the player's collider, so collisions resolve to the player directly
+    pub tag_id: u64,
+    /// Distance of the moves received since the last fixed step, which resolves them together
+    pub pending_move: [f32; 3],
 }
 
 impl Player {
-    pub fn new(id: String, username: String, addr: SocketAddr, body_handle: RigidBodyHandle, collider_handle: ColliderHandle) -> Self {
+    pub fn new(
+        id: String,
+        username: String,
+        addr: SocketAddr,
+        entity_id: EntityId,
+        tag_id: u64,
+    ) -> Self {
         Player {
             id,
             username,
-            body_handle,
-            collider_handle,
+            entity_id,
+            tag_id,
             server_info: PlayerServerInfo { addr },
             health: 100,
+            pending_move: [0.0; 3],
         }
     }
 }